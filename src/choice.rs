@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+use crate::{despawn_screen, GameState, TEXT_COLOR};
+use crate::controls::{Action, ActionInput, MenuDirection};
+use crate::game::{ChooseUpgrade, UpgradeChoice, UpgradeChoiceSet};
+use crate::replay::ReplayPlayback;
+use crate::sim::{roman_numeral, Modifiers};
+
+const CARD_COLOR: Color = Color::rgb(0.157, 0.8, 0.875);
+const SELECTED_CARD_COLOR: Color = Color::rgb(0.224, 0.278, 0.471);
+const CHOICE_BACKGROUND: Color = Color::rgba(0.224, 0.471, 0.659, 0.9);
+
+const CARD_WIDTH: f32 = 140.;
+const CARD_HEIGHT: f32 = 110.;
+const CARD_MARGIN: f32 = 8.;
+const TITLE_TEXT_SIZE: f32 = 30.;
+const CARD_NAME_TEXT_SIZE: f32 = 22.;
+const CARD_TEXT_SIZE: f32 = 18.;
+
+// Fixed shortcuts that are not in the ActionMap, they only mean something on this screen
+const CARD_KEYS: [KeyCode; 3] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3];
+
+pub struct ChoicePlugin;
+
+impl Plugin for ChoicePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SelectedCard>()
+            .add_systems(Update, (
+                choice_screen_setup.run_if(resource_added::<UpgradeChoice>()),
+                despawn_screen::<OnChoiceScreen>.run_if(resource_removed::<UpgradeChoice>()),
+            ).run_if(in_state(GameState::Game)))
+            .add_systems(Update, (
+                (choose_with_keys, choose_with_mouse)
+                    .in_set(UpgradeChoiceSet)
+                    .run_if(not(resource_exists::<ReplayPlayback>())),
+                highlight_selected_card,
+            ).run_if(in_state(GameState::Game).and_then(resource_exists::<UpgradeChoice>())))
+            .add_systems(OnExit(GameState::Game), despawn_screen::<OnChoiceScreen>);
+    }
+}
+
+// resources
+#[derive(Resource, Default)]
+struct SelectedCard(usize);
+
+// components
+#[derive(Component)]
+struct OnChoiceScreen;
+
+#[derive(Component)]
+struct UpgradeCard(usize);
+
+// systems
+fn choice_screen_setup(
+    mut commands: Commands,
+    choice: Res<UpgradeChoice>,
+    modifiers: Res<Modifiers>,
+    mut selected: ResMut<SelectedCard>,
+) {
+    selected.0 = 0;
+    let card_style = Style {
+        width: Val::Px(CARD_WIDTH),
+        height: Val::Px(CARD_HEIGHT),
+        margin: UiRect::all(Val::Px(CARD_MARGIN)),
+        flex_direction: FlexDirection::Column,
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let name_text_style = TextStyle {
+        font_size: CARD_NAME_TEXT_SIZE,
+        color: TEXT_COLOR,
+        ..default()
+    };
+    let text_style = TextStyle {
+        font_size: CARD_TEXT_SIZE,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: CHOICE_BACKGROUND.into(),
+                ..default()
+            },
+            OnChoiceScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Pick an upgrade",
+                TextStyle {
+                    font_size: TITLE_TEXT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (index, upgrade) in choice.options.iter().enumerate() {
+                        let level = modifiers.level(upgrade.id) + 1;
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: card_style.clone(),
+                                    background_color: CARD_COLOR.into(),
+                                    ..default()
+                                },
+                                UpgradeCard(index),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(upgrade.name, name_text_style.clone()));
+                                parent.spawn(TextBundle::from_section(
+                                    if level > 1 {
+                                        format!("level {}", roman_numeral(level))
+                                    } else {
+                                        "new".to_string()
+                                    },
+                                    text_style.clone(),
+                                ));
+                                parent.spawn(TextBundle::from_section(format!("[{}]", index + 1), text_style.clone()));
+                            });
+                    }
+                });
+
+            // Show what the next milestone will throw at the borb so the pick can account for it
+            let upcoming_chaos = match choice.upcoming_chaos {
+                Some(chaos) => format!("next chaos: {}", chaos.notification_at(modifiers.level(chaos.id) + 1)),
+                None => "no more chaos".to_string(),
+            };
+            parent.spawn(TextBundle::from_section(upcoming_chaos, text_style.clone()));
+        });
+}
+
+// Number keys pick a card directly, arrows, the d-pad or the stick move the selection and
+// confirm picks it
+fn choose_with_keys(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut action_input: ActionInput,
+    choice: Res<UpgradeChoice>,
+    mut selected: ResMut<SelectedCard>,
+    mut choose_events: EventWriter<ChooseUpgrade>,
+) {
+    if let Some(index) = CARD_KEYS.iter().position(|key| keyboard_input.just_pressed(*key)) {
+        choose_events.send(ChooseUpgrade(index));
+        return;
+    }
+
+    let last = choice.options.len().saturating_sub(1);
+    match action_input.navigation() {
+        Some(MenuDirection::Left) => selected.0 = selected.0.saturating_sub(1),
+        Some(MenuDirection::Right) => selected.0 = (selected.0 + 1).min(last),
+        _ => {}
+    }
+    if action_input.just_pressed(Action::Confirm) {
+        choose_events.send(ChooseUpgrade(selected.0));
+    }
+}
+
+fn choose_with_mouse(
+    interaction_query: Query<(&Interaction, &UpgradeCard), Changed<Interaction>>,
+    mut selected: ResMut<SelectedCard>,
+    mut choose_events: EventWriter<ChooseUpgrade>,
+) {
+    for (interaction, card) in &interaction_query {
+        match interaction {
+            Interaction::Pressed => choose_events.send(ChooseUpgrade(card.0)),
+            Interaction::Hovered => selected.0 = card.0,
+            Interaction::None => {}
+        }
+    }
+}
+
+fn highlight_selected_card(
+    mut card_query: Query<(&UpgradeCard, &mut BackgroundColor)>,
+    selected: Res<SelectedCard>,
+) {
+    for (card, mut color) in &mut card_query {
+        *color = if card.0 == selected.0 {
+            SELECTED_CARD_COLOR.into()
+        } else {
+            CARD_COLOR.into()
+        };
+    }
+}
@@ -0,0 +1,255 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::{Path, PathBuf}};
+use crate::data_dir;
+
+pub const MAX_BINDINGS: usize = 2;
+// How far a stick has to be pushed to move through a menu
+const STICK_THRESHOLD: f32 = 0.5;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        let action_map = match ActionMap::load(&controls_path()) {
+            Ok(action_map) => action_map,
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    warn!("could not load controls: {error}");
+                }
+                ActionMap::default()
+            }
+        };
+        app.insert_resource(action_map);
+    }
+}
+
+// What the player can ask for, gameplay and menus read these instead of keys
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
+pub enum Action {
+    Flap,
+    FastFall,
+    Hover,
+    BulletTime,
+    Glide,
+    Pause,
+    Confirm,
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::Flap,
+        Action::FastFall,
+        Action::Hover,
+        Action::BulletTime,
+        Action::Glide,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Flap => "Flap",
+            Action::FastFall => "Fast Fall",
+            Action::Hover => "Hover",
+            Action::BulletTime => "Bullet Time",
+            Action::Glide => "Glide",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+        }
+    }
+
+    // Menu actions are read on menus and the upgrade choice, the rest while playing
+    pub fn is_menu(self) -> bool {
+        matches!(self, Action::Confirm | Action::Back)
+    }
+
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Action::Flap => vec![KeyCode::Space, KeyCode::Up],
+            Action::FastFall => vec![KeyCode::ControlLeft, KeyCode::Down],
+            Action::Hover => vec![KeyCode::AltLeft],
+            Action::BulletTime => vec![KeyCode::B],
+            Action::Glide => vec![KeyCode::ShiftLeft],
+            Action::Pause => vec![KeyCode::Escape, KeyCode::P],
+            Action::Confirm => vec![KeyCode::Return, KeyCode::Space],
+            Action::Back => vec![KeyCode::Escape, KeyCode::Back],
+        }
+    }
+
+    fn default_buttons(self) -> Vec<GamepadButtonType> {
+        match self {
+            Action::Flap => vec![GamepadButtonType::South],
+            Action::FastFall => vec![GamepadButtonType::RightTrigger2, GamepadButtonType::LeftTrigger2],
+            Action::Hover => vec![GamepadButtonType::West],
+            Action::BulletTime => vec![GamepadButtonType::North],
+            Action::Glide => vec![GamepadButtonType::RightTrigger, GamepadButtonType::LeftTrigger],
+            Action::Pause => vec![GamepadButtonType::Start],
+            Action::Confirm => vec![GamepadButtonType::South],
+            Action::Back => vec![GamepadButtonType::East, GamepadButtonType::Select],
+        }
+    }
+}
+
+// Where to move through a menu
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum MenuDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl MenuDirection {
+    // In UI coordinates, where y grows downwards
+    pub fn vector(self) -> Vec2 {
+        match self {
+            MenuDirection::Up => Vec2::NEG_Y,
+            MenuDirection::Down => Vec2::Y,
+            MenuDirection::Left => Vec2::NEG_X,
+            MenuDirection::Right => Vec2::X,
+        }
+    }
+}
+
+// Keys bound to every action, up to MAX_BINDINGS each, and the gamepad buttons that work on
+// top of them. A key belongs to at most one gameplay and one menu action. The two sides do overlap:
+// Escape both pauses and goes back, and on the upgrade choice Space confirms as well as flapping,
+// which is why picking an upgrade throws away the borb's input.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct ActionMap {
+    pub bindings: BTreeMap<Action, Vec<KeyCode>>,
+    #[serde(default)]
+    pub buttons: BTreeMap<Action, Vec<GamepadButtonType>>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL.into_iter().map(|action| (action, action.default_keys())).collect(),
+            buttons: Action::ALL.into_iter().map(|action| (action, action.default_buttons())).collect(),
+        }
+    }
+}
+
+impl ActionMap {
+    // Actions missing from an older file get their default keys
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut action_map: Self = ron::from_str(&text)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        for action in Action::ALL {
+            action_map.bindings.entry(action).or_insert_with(|| action.default_keys());
+            action_map.buttons.entry(action).or_insert_with(|| action.default_buttons());
+        }
+        Ok(action_map)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = ron::ser::to_string_pretty(self, default())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::write(path, text)
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    pub fn buttons(&self, action: Action) -> &[GamepadButtonType] {
+        self.buttons.get(&action).map_or(&[], |buttons| buttons.as_slice())
+    }
+
+    // Puts `key` in the given slot, or after the action's other keys if the slot is still empty.
+    // Keys already taken by another action read alongside this one are left where they are
+    pub fn rebind(&mut self, action: Action, slot: usize, key: KeyCode) {
+        let taken = self
+            .bindings
+            .iter()
+            .any(|(&other, keys)| other != action && other.is_menu() == action.is_menu() && keys.contains(&key));
+        let keys = self.bindings.entry(action).or_default();
+        if taken || keys.contains(&key) {
+            return;
+        }
+        if let Some(bound) = keys.get_mut(slot) {
+            *bound = key;
+        } else if keys.len() < MAX_BINDINGS {
+            keys.push(key);
+        }
+    }
+}
+
+pub fn controls_path() -> PathBuf {
+    data_dir().join("controls.ron")
+}
+
+// Reads actions from the keyboard and every connected gamepad through the ActionMap
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    action_map: Res<'w, ActionMap>,
+    keyboard_input: Res<'w, Input<KeyCode>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_input: Res<'w, Input<GamepadButton>>,
+    gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+    // Where the stick pointed last time, holding it only moves once
+    stick: Local<'s, Option<MenuDirection>>,
+}
+
+impl ActionInput<'_, '_> {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.keyboard_input.any_pressed(self.action_map.keys(action).iter().copied())
+            || self.gamepad_buttons(action).any(|button| self.gamepad_input.pressed(button))
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.keyboard_input.any_just_pressed(self.action_map.keys(action).iter().copied())
+            || self.gamepad_buttons(action).any(|button| self.gamepad_input.just_pressed(button))
+    }
+
+    // The arrow key, d-pad or left stick direction pushed since last time
+    pub fn navigation(&mut self) -> Option<MenuDirection> {
+        let arrows = [
+            (KeyCode::Up, GamepadButtonType::DPadUp, MenuDirection::Up),
+            (KeyCode::Down, GamepadButtonType::DPadDown, MenuDirection::Down),
+            (KeyCode::Left, GamepadButtonType::DPadLeft, MenuDirection::Left),
+            (KeyCode::Right, GamepadButtonType::DPadRight, MenuDirection::Right),
+        ];
+        let pushed = arrows.into_iter().find(|(key, button_type, _)| {
+            self.keyboard_input.just_pressed(*key)
+                || self
+                    .gamepads
+                    .iter()
+                    .any(|gamepad| self.gamepad_input.just_pressed(GamepadButton::new(gamepad, *button_type)))
+        });
+
+        let stick = self.gamepads.iter().find_map(|gamepad| {
+            let x = self.gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.);
+            let y = self.gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.);
+            if x.abs().max(y.abs()) < STICK_THRESHOLD {
+                None
+            } else if x.abs() > y.abs() {
+                Some(if x > 0. { MenuDirection::Right } else { MenuDirection::Left })
+            } else {
+                Some(if y > 0. { MenuDirection::Up } else { MenuDirection::Down })
+            }
+        });
+        let stick_moved = stick.filter(|_| *self.stick != stick);
+        *self.stick = stick;
+
+        pushed.map(|(_, _, direction)| direction).or(stick_moved)
+    }
+
+    fn gamepad_buttons(&self, action: Action) -> impl Iterator<Item = GamepadButton> + '_ {
+        self.gamepads.iter().flat_map(move |gamepad| {
+            self.action_map
+                .buttons(action)
+                .iter()
+                .map(move |button_type| GamepadButton::new(gamepad, *button_type))
+        })
+    }
+}
@@ -1,39 +1,38 @@
 use bevy::{
+    ecs::{component::TableStorage, system::SystemParam},
+    input::InputSystem,
     prelude::*,
-    sprite::collide_aabb::collide,
+    ui::UiSystem,
 };
 use std::f32::consts::PI;
-use rand::random;
-use crate::{GameState, SCALE, SCREEN_SIZE, despawn_screen, GameOverCause, MenuState, HALF_SCREEN_SIZE, TEXT_COLOR};
+use rand::{random, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use crate::{GameState, SCALE, despawn_screen, GameOverCause, MenuState, TEXT_COLOR};
+use crate::controls::{Action, ActionInput, ActionMap};
+use crate::interpolation::{Interpolated, SimulationSet};
+use crate::sim::{
+    self, Aabb, BulletTime, ControlScramble, Darkness, Glide, Gravity, GravityFlip, Hover, ModifierDef, ModifierKind, Modifiers, ObstacleGenerator,
+    OscillatingPipe, PipeEvent, PipeType, Scoreboard, Scramble, Wind, WindPhase,
+    HALF_PIPE_SPACE, HALF_SCREEN_HEIGHT, HALF_SCREEN_WIDTH_WITH_HALF_PIPE, SCREEN_WIDTH, SCREEN_WIDTH_WITH_PIPE,
+    UPGRADE_CHOICES,
+};
 
 // consts
-const PIPE_SPEED: f32 = 50.0;
-const HALF_PIPE_SPACE: f32 = 50.0;
-const MAX_PIPE_HOLE_Y: f32 = 80.;
+pub const TICKS_PER_SECOND: f64 = 60.0;
+
+const GAMEPLAY_RNG_STREAM: u64 = 0;
+const COSMETIC_RNG_STREAM: u64 = 1;
+
 const NUM_PIPES: u32 = 3;
-const PIPE_SIZE: Vec2 = Vec2 { x: SCALE.x * 16., y: SCALE.y * 64. };
 const PIPE_COLLIDER: Vec2 = Vec2 { x: SCALE.x * 12., y: SCALE.y * 64. };
 const MIDDLE_PIPE_COOLLIDER: Vec2 = Vec2 { x: SCALE.x * 12., y: SCALE.y * 48. };
 
-const BORB_START_POS: Vec3 = Vec3 { x: -200.0, y: 0.0, z: 0.0 };
+pub const BORB_START_POS: Vec3 = Vec3 { x: -200.0, y: 0.0, z: 0.0 };
 const BORB_COLLIDER: Vec2 = Vec2 { x: SCALE.x * 10., y: SCALE.y * 10. };
 const BORB_SIZE: Vec2 = Vec2 { x: SCALE.x * 12., y: SCALE.y * 12. };
 const BORB_HALF_HEIGHT: f32 = BORB_SIZE.y / 2.;
-const DEGREES_PER_GRAVITY: f32 = 0.006381360077604268;
-
-const GRAVITY: f32 = 140.0;
-const MAX_GRAVITY: f32 = -140.0;
-const JUMP_FORCE: f32 = 110.0;
-
-const SCREEN_WIDTH_WITH_PIPE: f32 = SCREEN_SIZE.x + PIPE_SIZE.x;
-const HALF_SCREEN_WIDTH_WITH_HALF_PIPE: f32 = SCREEN_WIDTH_WITH_PIPE / 2.;
-
-const MAX_UPGRADES: u32 = 5;
-const MAX_CHAOS: u32 = 5;
-const PIPES_PER_UPGRADE: u32 = 5;
-
-const FAST_FALL_SPEED: f32 = -160.;
-const FAST_PIPE_SPEED: f32 = 75.;
+pub const DEGREES_PER_GRAVITY: f32 = 0.006_381_36;
 
 const NOTIFICATION_TEXT_SIZE: f32 = 25.;
 const NOTIFICATION_START_X: f32 = 10.;
@@ -53,173 +52,330 @@ const JUMP_PARTICLE_DISTANCE: Vec2 = Vec2 { x: BORB_COLLIDER.x / (JUMP_PARTICLE_
 const JUMP_PARTICLE_DIRECTION: Vec2 = Vec2::NEG_Y;
 const FAST_FALL_PARTICLE_RELATIVE_START_POS: Vec2 = Vec2 { x: -BORB_COLLIDER.x / 2., y: BORB_COLLIDER.y / 2. };
 const FAST_FALL_PARTICLE_DIRECTION: Vec2 = Vec2::Y;
+const SHIELD_PARTICLE_NUM: u32 = 12;
+const GLIDE_PARTICLE_INTERVAL: u32 = 6;
+const GLIDE_PARTICLE_RELATIVE_START_POS: Vec2 = Vec2 { x: -BORB_COLLIDER.x / 2., y: 0. };
+const GLIDE_PARTICLE_DIRECTION: Vec2 = Vec2::NEG_X;
+const HOVER_PARTICLE_NUM: u32 = 3;
+const HOVER_PARTICLE_SPREAD: f32 = 0.4;
+
+// A second finger this soon after a tap turns it into a two finger tap
+const TWO_FINGER_TAP_WINDOW: f32 = 0.1;
+
+const INVULNERABILITY_TIME: f32 = 1.5;
+const INVULNERABILITY_BLINK_TIME: f32 = 0.1;
+
+const SHIELD_ICON_SIZE: f32 = SCALE.x * 8.;
+const SHIELD_ICON_MARGIN: f32 = 10.;
+
+const STAMINA_BAR_WIDTH: f32 = 60.;
+const STAMINA_BAR_HEIGHT: f32 = 6.;
+const STAMINA_BAR_TOP: f32 = SHIELD_ICON_MARGIN * 2. + SHIELD_ICON_SIZE;
+const STAMINA_BAR_BACKGROUND: Color = Color::rgb(0.224, 0.278, 0.471);
+const STAMINA_BAR_COLOR: Color = Color::rgb(0.157, 0.8, 0.875);
+
+const BULLET_TIME_TINT: Color = Color::rgba(0.35, 0.2, 0.6, 0.3);
+
+const WIND_STREAK_INTERVAL: u32 = 4;
+const WIND_ARROW_POS: Vec3 = Vec3 { x: 0., y: HALF_SCREEN_HEIGHT - 25., z: 1. };
+const WIND_ARROW_BLINK_TIME: f32 = 0.15;
+
+const GRAVITY_COUNTDOWN_TOP: f32 = 60.;
+const GRAVITY_COUNTDOWN_TEXT_SIZE: f32 = 25.;
+
+const PAUSE_TEXT_SIZE: f32 = 40.;
+const PAUSE_BACKGROUND: Color = Color::rgba(0.224, 0.471, 0.659, 0.6);
+
+const DARKNESS_COLOR: Color = Color::rgba(0.02, 0.02, 0.06, 0.94);
+const DARKNESS_Z: f32 = 0.5;
+// Big enough to reach the far edges of the screen from anywhere the borb can be
+const DARKNESS_COVER: f32 = SCREEN_WIDTH * 2.;
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app
+            .insert_resource(Time::<Fixed>::from_hz(TICKS_PER_SECOND))
+            .init_resource::<NextSeed>()
+            .init_resource::<GameAssets>()
+            .init_resource::<GameMode>()
+            .init_resource::<TimeScale>()
+            .init_resource::<ActionMap>()
+            .init_resource::<GameOver>()
+            .add_event::<GameOverEvent>()
+            .add_event::<ChooseUpgrade>()
+            .configure_sets(FixedUpdate, InputSet.in_set(SimulationSet::Simulate))
+            // The world stands still while an upgrade is being picked or the run is paused, and stops
+            // for good at the first game over even if the frame still has ticks left to run
+            .configure_sets(FixedUpdate, (
+                SimulationSet::Restore,
+                SimulationSet::Simulate,
+                SimulationSet::Capture,
+            ).run_if(
+                not(resource_exists::<UpgradeChoice>())
+                    .and_then(not(resource_exists::<Paused>()))
+                    .and_then(still_running),
+            ))
             .add_systems(OnEnter(GameState::Game), setup)
+            .add_systems(PreUpdate, read_input
+                .after(InputSystem)
+                .after(UiSystem::Focus)
+                .run_if(in_state(GameState::Game)))
             .add_systems(Update, (
+                update_notifications,
+                blink_invulnerable_borb,
+                update_shield_icon,
+                update_stamina_bar,
+                update_bullet_time_effects,
+                update_wind_arrow,
+                update_gravity_countdown,
+                toggle_pause.run_if(not(resource_exists::<UpgradeChoice>())),
+            ).run_if(in_state(GameState::Game)))
+            .add_systems(Update, (
+                pause_for_upgrade_choice.run_if(resource_added::<UpgradeChoice>()),
+                apply_upgrade_choice.after(UpgradeChoiceSet).run_if(resource_exists::<UpgradeChoice>()),
+            ).chain().run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, advance_tick
+                .in_set(SimulationSet::Restore)
+                .run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, (
                 (
+                    update_time_scale.after(InputSet),
+                    flip_gravity,
+                    scramble_controls,
                     jump,
+                    glide_and_hover,
                     apply_gravity,
+                    blow_wind,
+                    resize_borb,
                     move_pipes,
+                    update_invulnerability,
                     check_for_collisions,
                     check_out_of_bounds,
+                    reach_milestones,
                     update_borb_rotation,
+                    update_darkness,
                 ).chain(),
-                update_notifications,
                 update_particles,
-            ).run_if(in_state(GameState::Game)))
-            .add_systems(OnExit(GameState::Game), despawn_screen::<OnGameScreen>);
+            ).in_set(SimulationSet::Simulate).run_if(in_state(GameState::Game)))
+            .add_systems(OnExit(GameState::Game), (despawn_screen::<OnGameScreen>, clear_pause, clear_upgrade_choice));
     }
 }
 
+// Systems that fill in BorbInput before the borb reacts to it each tick
+#[derive(SystemSet, Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub struct InputSet;
+
+// Systems that send ChooseUpgrade while the choice is open, before it is applied
+#[derive(SystemSet, Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub struct UpgradeChoiceSet;
+
 //enums
+#[derive(Component, Clone, Copy, Eq, PartialEq)]
 enum PipeSide {
     Top,
     Bottom,
     Center,
 }
 
-#[derive(Component, PartialEq, PartialOrd)]
-enum PipeType {
-    Normal,
-    Middle,
+// The gameplay rules live in `sim` without Bevy, these make them usable as resources and components
+impl Resource for Scoreboard {}
+impl Resource for Modifiers {}
+impl Resource for ObstacleGenerator {}
+impl Resource for BulletTime {}
+impl Resource for Wind {}
+impl Resource for GravityFlip {}
+impl Resource for Darkness {}
+impl Resource for ControlScramble {}
+
+impl Component for Gravity {
+    type Storage = TableStorage;
+}
+
+impl Component for Glide {
+    type Storage = TableStorage;
+}
+
+impl Component for Hover {
+    type Storage = TableStorage;
+}
+
+impl Component for PipeType {
+    type Storage = TableStorage;
+}
+
+impl Component for OscillatingPipe {
+    type Storage = TableStorage;
 }
 
 // resources
+// Handles stay empty when there is no asset server, like in headless runs
 #[derive(Resource)]
-struct CollisionSound(Handle<AudioSource>);
+pub struct GameAssets {
+    pub borb: Handle<Image>,
+    pub pipe: Handle<Image>,
+    pub middle_pipe: Handle<Image>,
+    pub particle: Handle<Image>,
+    pub shield: Handle<Image>,
+    pub arrow: Handle<Image>,
+    pub darkness: Handle<Image>,
+    pub game_over_sound: Handle<AudioSource>,
+}
 
-#[derive(Resource)]
-pub struct Scoreboard {
-    pub score: u32,
-    was_last_upgrade_good: bool,
-}
-
-impl Scoreboard {
-    fn add(
-        &mut self, score: u32,
-        upgrades: &mut ResMut<BorbUpgrades>,
-        chaos: &mut ResMut<WorldChaos>,
-        commands: &mut Commands,
-    ) {
-        let num_upgrades = self.score / PIPES_PER_UPGRADE;
-        self.score += score;
-        for _ in 0..(self.score / PIPES_PER_UPGRADE - num_upgrades) {
-            self.upgrade(upgrades, chaos, commands);
+impl FromWorld for GameAssets {
+    fn from_world(world: &mut World) -> Self {
+        let Some(asset_server) = world.get_resource::<AssetServer>() else {
+            return Self {
+                borb: default(),
+                pipe: default(),
+                middle_pipe: default(),
+                particle: default(),
+                shield: default(),
+                arrow: default(),
+                darkness: default(),
+                game_over_sound: default(),
+            };
+        };
+        Self {
+            borb: asset_server.load("sprites/borb.png"),
+            pipe: asset_server.load("sprites/pipe.png"),
+            middle_pipe: asset_server.load("sprites/middle_pipe.png"),
+            particle: asset_server.load("sprites/particle.png"),
+            shield: asset_server.load("sprites/shield.png"),
+            arrow: asset_server.load("sprites/arrow.png"),
+            darkness: asset_server.load("sprites/darkness.png"),
+            game_over_sound: asset_server.load("sounds/game_over.wav"),
         }
     }
+}
 
-    fn upgrade(
-        &mut self,
-        upgrades: &mut ResMut<BorbUpgrades>,
-        chaos: &mut ResMut<WorldChaos>,
-        commands: &mut Commands,
-    ) {
-        if self.was_last_upgrade_good {
-            chaos.upgrade(commands);
-        } else {
-            upgrades.upgrade(commands);
-        }
-        self.was_last_upgrade_good = !self.was_last_upgrade_good
-    }
+// How the current run was started, kept in the high score table
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub enum GameMode {
+    #[default]
+    Normal,
+    ChosenSeed,
+    GhostRace,
 }
 
+// Seed to use for the next run, a random one is picked if none was chosen
+#[derive(Resource, Default)]
+pub struct NextSeed(pub Option<u64>);
+
+// Gameplay and cosmetic randomness are separate streams so particles never change how a run plays out.
+// Chaos effects only draw from the gameplay stream while they are active, so a run without them
+// plays out the same as before they existed
 #[derive(Resource)]
-struct BorbUpgrades {
-    num_upgrades: u32,
-    fast_fall: bool,
+pub struct GameRng {
+    pub seed: u64,
+    gameplay: ChaCha8Rng,
+    cosmetic: ChaCha8Rng,
 }
 
-impl Default for BorbUpgrades {
-    fn default() -> Self {
-        Self {
-            num_upgrades: 0,
-            fast_fall: false,
-        }
+impl GameRng {
+    fn new(seed: u64) -> Self {
+        let mut gameplay = ChaCha8Rng::seed_from_u64(seed);
+        gameplay.set_stream(GAMEPLAY_RNG_STREAM);
+        let mut cosmetic = ChaCha8Rng::seed_from_u64(seed);
+        cosmetic.set_stream(COSMETIC_RNG_STREAM);
+        Self { seed, gameplay, cosmetic }
     }
 }
 
-impl BorbUpgrades {
-    fn upgrade(&mut self, commands: &mut Commands) {
-        if self.num_upgrades != MAX_UPGRADES {
-            self.fast_fall = true;
-            self.num_upgrades += 1;
-            create_notification("fast fall", commands);
+// Inputs gathered every frame and consumed by the next simulation tick
+#[derive(Resource, Default)]
+pub struct BorbInput {
+    pub jump: bool,
+    pub fast_fall: bool,
+    pub hover: bool,
+    pub bullet_time: bool,
+    // Held rather than pressed, so it is kept between ticks
+    pub glide: bool,
+}
+
+impl BorbInput {
+    // Menu actions are not for the borb and do nothing here
+    pub fn press(&mut self, action: Action) {
+        match action {
+            Action::Flap => self.jump = true,
+            Action::FastFall => self.fast_fall = true,
+            Action::Hover => self.hover = true,
+            Action::BulletTime => self.bullet_time = true,
+            Action::Glide => self.glide = true,
+            Action::Pause | Action::Confirm | Action::Back => {}
         }
     }
 }
 
+// Number of simulation ticks since the run started, counted up at the start of each tick
+#[derive(Resource, Default)]
+pub struct SimulationTick(pub u32);
+
+// How fast the world runs compared to the simulation, slowed down by bullet time
 #[derive(Resource)]
-struct WorldChaos {
-    num_chaos: u32,
-    fast_pipes: bool,
-    different_pipes: bool,
-    world_speed: f32,
-}
+pub struct TimeScale(pub f32);
 
-impl Default for WorldChaos {
+impl Default for TimeScale {
     fn default() -> Self {
-        Self {
-            num_chaos: 0,
-            fast_pipes: false,
-            different_pipes: false,
-            world_speed: PIPE_SPEED,
-        }
+        Self(1.)
     }
 }
 
-impl WorldChaos {
-    fn upgrade(&mut self, commands: &mut Commands) {
-        if self.num_chaos != MAX_CHAOS {
-            self.num_chaos += 1;
-            if self.fast_pipes {
-                self.different_pipes = true;
-                create_notification("different pipes", commands);
-            } else {
-                self.fast_pipes = true;
-                self.world_speed = FAST_PIPE_SPEED;
-                create_notification("fast pipes", commands);
-            }
-        }
+// Time as the world experiences it, the systems moving things around read this instead of `Time`
+#[derive(SystemParam)]
+pub struct GameTime<'w> {
+    time: Res<'w, Time>,
+    scale: Res<'w, TimeScale>,
+}
+
+impl GameTime<'_> {
+    pub fn delta_seconds(&self) -> f32 {
+        self.time.delta_seconds() * self.scale.0
     }
 }
 
+// Present while the game is paused on an upgrade milestone, along with the chaos the next milestone brings
 #[derive(Resource)]
-struct ObstacleGenerator {
-    last_generated: PipeType,
+pub struct UpgradeChoice {
+    pub options: Vec<&'static ModifierDef>,
+    pub upcoming_chaos: Option<&'static ModifierDef>,
 }
 
-impl ObstacleGenerator {
-    fn next_obstacle(&mut self, chaos: &ResMut<WorldChaos>) -> PipeType {
-        if chaos.different_pipes && self.last_generated != PipeType::Middle && random::<f32>() < 0.2 {
-            self.last_generated = PipeType::Middle;
-            return PipeType::Middle;
-        } else {
-            self.last_generated = PipeType::Normal;
-            return PipeType::Normal;
-        }
-    }
-}
+// events
+// Sent once per run, when the borb dies
+#[derive(Event)]
+pub struct GameOverEvent(pub GameOverCause);
+
+// Present while the player has paused the run
+#[derive(Resource)]
+pub struct Paused;
+
+// Milestones reached by passing pipes this tick, handed out once the tick is survived
+#[derive(Resource, Default)]
+struct Milestones(Vec<ModifierKind>);
+
+// Set by the first game over of a run, the state only changes on the next frame
+#[derive(Resource, Default)]
+pub struct GameOver(pub Option<GameOverCause>);
+
+// Picks one of the UpgradeChoice options by index
+#[derive(Event)]
+pub struct ChooseUpgrade(pub usize);
 
 // components
 #[derive(Component)]
-struct OnGameScreen;
+pub struct OnGameScreen;
 
 #[derive(Component)]
-struct Borb;
+pub struct Borb;
 
 #[derive(Component)]
-struct Gravity {
-    gravity: f32,
-    from_fast_fall: bool,
-}
+struct Collider(Vec2);
 
+// How big the borb currently is compared to its sprite, eased towards the size from shrink
 #[derive(Component)]
-struct Collider(Vec2);
+struct BorbSize(f32);
 
 #[derive(Component)]
 struct Obstacle(GameOverCause);
@@ -230,6 +386,41 @@ struct PipeParent(bool);
 #[derive(Component)]
 struct Notification(f32);
 
+// Seconds left after a shield broke during which pipes can not hurt the borb
+#[derive(Component)]
+struct Invulnerable(f32);
+
+#[derive(Component)]
+struct ShieldIcon;
+
+#[derive(Component)]
+struct StaminaBar;
+
+#[derive(Component)]
+struct BulletTimeTint;
+
+#[derive(Component)]
+struct WindArrow;
+
+#[derive(Component)]
+struct GravityCountdown;
+
+#[derive(Component)]
+struct OnPauseScreen;
+
+// Follows the borb while darkness is active, its parts cover everything outside of the radius
+#[derive(Component)]
+struct DarknessOverlay;
+
+#[derive(Component, Clone, Copy)]
+enum DarknessPart {
+    Hole,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
 #[derive(Component)]
 struct Particle {
     speed: f32,
@@ -244,17 +435,21 @@ struct PipeBundle {
     collider: Collider,
     enemy: Obstacle,
     pipe_type: PipeType,
+    side: PipeSide,
+    // Moving pipes open and close the gap, so the pipes move inside their pair too
+    interpolated: Interpolated,
 }
 
 impl PipeBundle {
     fn new(position: Vec2, texture: Handle<Image>, side: PipeSide, pipe_type: PipeType) -> PipeBundle {
+        let transform = Transform {
+            translation: position.extend(0.0),
+            scale: Vec3 { x: 1.0, y: 1.0, z: 1.0 },
+            ..default()
+        };
         PipeBundle {
             sprite: SpriteBundle {
-                transform: Transform {
-                    translation: position.extend(0.0),
-                    scale: Vec3 { x: 1.0, y: 1.0, z: 1.0 },
-                    ..default()
-                },
+                transform,
                 texture,
                 visibility: match pipe_type {
                     PipeType::Normal => Visibility::Visible,
@@ -275,20 +470,26 @@ impl PipeBundle {
             }),
             enemy: Obstacle(GameOverCause::HitPipe),
             pipe_type,
+            side,
+            interpolated: Interpolated::new(&transform),
         }
     }
 }
 
 // functions
-fn random_pipe_hole_y() -> f32 {
-    random::<f32>() * MAX_PIPE_HOLE_Y * 2.0 - MAX_PIPE_HOLE_Y
+fn collider_box(translation: Vec3, collider: &Collider) -> Aabb {
+    Aabb::new(translation.x, translation.y, collider.0.x, collider.0.y)
 }
 
 fn game_over(
     cause: GameOverCause,
+    run_over: &mut GameOver,
     game_state: &mut ResMut<NextState<GameState>>,
     menu_state: &mut ResMut<NextState<MenuState>>,
+    game_over_events: &mut EventWriter<GameOverEvent>,
 ) {
+    run_over.0 = Some(cause);
+    game_over_events.send(GameOverEvent(cause));
     game_state.set(GameState::Menu);
     menu_state.set(MenuState::GameOver(cause));
 }
@@ -323,23 +524,25 @@ fn create_particle(
     position: Vec2,
     direction: Vec2,
     commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
+    assets: &GameAssets,
+    rng: &mut GameRng,
 ) {
-    let particle_texture = asset_server.load("sprites/particle.png");
+    let transform = Transform {
+        translation: position.extend(0.0),
+        rotation: Quat::from_rotation_z(PI * rng.cosmetic.gen::<f32>()),
+        scale: SCALE,
+    };
     commands.spawn((
         SpriteBundle {
-            transform: Transform {
-                translation: position.extend(0.0),
-                rotation: Quat::from_rotation_z(PI * random::<f32>()),
-                scale: SCALE,
-            },
-            texture: particle_texture,
+            transform,
+            texture: assets.particle.clone(),
             ..default()
         },
+        Interpolated::new(&transform),
         Particle {
             speed: PARTICLE_START_SPEED,
             direction: direction.extend(0.0),
-            rotation_speed: (random::<f32>() * PARTICLE_MAX_ROTATION + PARTICLE_ADD_ROTATION) * match random::<bool>() {
+            rotation_speed: (rng.cosmetic.gen::<f32>() * PARTICLE_MAX_ROTATION + PARTICLE_ADD_ROTATION) * match rng.cosmetic.gen::<bool>() {
                 true => 1.,
                 false => -1.,
             }
@@ -351,48 +554,204 @@ fn create_particle(
 // systems
 fn setup(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    mut next_seed: ResMut<NextSeed>,
 ) {
-    commands.insert_resource(Scoreboard { score: 0, was_last_upgrade_good: true });
-    commands.insert_resource(WorldChaos::default());
-    commands.insert_resource(BorbUpgrades::default());
-    commands.insert_resource(ObstacleGenerator { last_generated: PipeType::Normal });
+    let seed = next_seed.0.take().unwrap_or_else(|| random::<u32>() as u64);
+    let mut rng = GameRng::new(seed);
 
-    let game_over_sound = asset_server.load("sounds/game_over.wav");
-    commands.insert_resource(CollisionSound(game_over_sound));
+    commands.insert_resource(Scoreboard::default());
+    commands.insert_resource(Modifiers::default());
+    commands.insert_resource(ObstacleGenerator::default());
+    commands.insert_resource(BorbInput::default());
+    commands.insert_resource(SimulationTick::default());
+    commands.insert_resource(BulletTime::default());
+    commands.insert_resource(TimeScale::default());
+    commands.insert_resource(Wind::default());
+    commands.insert_resource(GravityFlip::default());
+    commands.insert_resource(Darkness::default());
+    commands.insert_resource(ControlScramble::default());
+    commands.insert_resource(GameOver::default());
+    commands.insert_resource(Milestones::default());
 
     // Borb
-    let borb_texture = asset_server.load("sprites/borb.png");
+    let borb_transform = Transform::from_translation(BORB_START_POS).with_scale(SCALE);
     commands.spawn((
         SpriteBundle {
-            texture: borb_texture,
-            transform: Transform::from_translation(BORB_START_POS).with_scale(SCALE),
+            texture: assets.borb.clone(),
+            transform: borb_transform,
             ..default()
         },
+        Interpolated::new(&borb_transform),
         Borb,
-        Gravity { gravity: 0.0, from_fast_fall: false },
+        Gravity::default(),
+        Glide::default(),
+        Hover::default(),
+        BorbSize(1.),
         Collider(BORB_COLLIDER),
         OnGameScreen,
     ));
 
+    let darkness_transform = Transform::from_translation(BORB_START_POS.truncate().extend(DARKNESS_Z));
+    commands
+        .spawn((
+            SpatialBundle {
+                transform: darkness_transform,
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Interpolated::new(&darkness_transform),
+            DarknessOverlay,
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            for part in [DarknessPart::Hole, DarknessPart::Left, DarknessPart::Right, DarknessPart::Top, DarknessPart::Bottom] {
+                parent.spawn((
+                    SpriteBundle {
+                        texture: match part {
+                            DarknessPart::Hole => assets.darkness.clone(),
+                            _ => default(),
+                        },
+                        sprite: Sprite {
+                            color: DARKNESS_COLOR,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    part,
+                ));
+            }
+        });
+
+    // Points where the next gust will blow
+    commands.spawn((
+        SpriteBundle {
+            texture: assets.arrow.clone(),
+            transform: Transform::from_translation(WIND_ARROW_POS).with_scale(SCALE),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        WindArrow,
+        OnGameScreen,
+    ));
+
+    commands.spawn((
+        ImageBundle {
+            image: UiImage::new(assets.shield.clone()),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(SHIELD_ICON_MARGIN),
+                left: Val::Px(SHIELD_ICON_MARGIN),
+                width: Val::Px(SHIELD_ICON_SIZE),
+                height: Val::Px(SHIELD_ICON_SIZE),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        ShieldIcon,
+        OnGameScreen,
+    ));
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            background_color: BULLET_TIME_TINT.into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        BulletTimeTint,
+        OnGameScreen,
+    ));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(GRAVITY_COUNTDOWN_TOP),
+                    width: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font_size: GRAVITY_COUNTDOWN_TEXT_SIZE,
+                            color: TEXT_COLOR,
+                            ..default()
+                        },
+                    ),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                GravityCountdown,
+            ));
+        });
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(STAMINA_BAR_TOP),
+                    left: Val::Px(SHIELD_ICON_MARGIN),
+                    width: Val::Px(STAMINA_BAR_WIDTH),
+                    height: Val::Px(STAMINA_BAR_HEIGHT),
+                    ..default()
+                },
+                background_color: STAMINA_BAR_BACKGROUND.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            StaminaBar,
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                background_color: STAMINA_BAR_COLOR.into(),
+                ..default()
+            });
+        });
+
     // Pipes
-    let pipe = asset_server.load("sprites/pipe.png");
-    let middle_pipe = asset_server.load("sprites/middle_pipe.png");
+    let pipe = &assets.pipe;
+    let middle_pipe = &assets.middle_pipe;
     let x_diff = SCREEN_WIDTH_WITH_PIPE / NUM_PIPES as f32;
     for pipe_num in 0..NUM_PIPES {
         let x = -HALF_SCREEN_WIDTH_WITH_HALF_PIPE + x_diff * (pipe_num as f32 + 1.);
-        let y = random_pipe_hole_y();
+        let y = sim::random_pipe_hole_y(&mut rng.gameplay);
+        let transform = Transform {
+            translation: Vec3 { x, y, z: 0.0 },
+            scale: SCALE,
+            ..Default::default()
+        };
         commands
             .spawn((
                 PipeParent(false),
+                OscillatingPipe::new(PipeType::Normal, y, HALF_PIPE_SPACE, None, &mut rng.gameplay),
                 SpatialBundle {
-                    transform: Transform {
-                        translation: Vec3 { x, y, z: 0.0 },
-                        scale: SCALE,
-                        ..Default::default()
-                    },
+                    transform,
                     ..Default::default()
                 },
+                Interpolated::new(&transform),
                 OnGameScreen,
             ))
             .with_children(|parent| {
@@ -401,77 +760,246 @@ fn setup(
                 parent.spawn(PipeBundle::new(Vec2 { x: 0., y: 0. }, middle_pipe.clone(), PipeSide::Center, PipeType::Middle));
             });
     }
+
+    commands.insert_resource(rng);
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn move_pipes(
-    mut commands: Commands,
-    mut query: Query<(&Children, &mut Transform, &mut PipeParent)>,
-    mut pipe_query: Query<(&mut Visibility, &PipeType), (Without<PipeParent>, Without<Borb>)>,
-    time: Res<Time>,
+    mut milestones: ResMut<Milestones>,
+    mut query: Query<(&Children, &mut Transform, &mut Interpolated, &mut PipeParent, &mut OscillatingPipe)>,
+    mut pipe_query: Query<
+        (&mut Transform, &mut Interpolated, &mut Visibility, &PipeType, &PipeSide),
+        (Without<PipeParent>, Without<Borb>),
+    >,
+    borb_query: Query<&Transform, (With<Borb>, Without<PipeParent>)>,
+    time: GameTime,
     mut score: ResMut<Scoreboard>,
-    mut chaos: ResMut<WorldChaos>,
-    mut upgrades: ResMut<BorbUpgrades>,
+    modifiers: Res<Modifiers>,
     mut obstacle_generator: ResMut<ObstacleGenerator>,
-
+    mut rng: ResMut<GameRng>,
 ) {
-    for (children, mut transform, mut pipe) in &mut query {
-        transform.translation.x -= chaos.world_speed * time.delta_seconds();
-        if transform.translation.x < -HALF_SCREEN_WIDTH_WITH_HALF_PIPE {
-            let obstacle = obstacle_generator.next_obstacle(&chaos);
-            for child in children.iter() {
-                if let Ok((mut visibility, pipe_type)) = pipe_query.get_mut(*child) {
-                    if pipe_type == &obstacle {
-                        *visibility = Visibility::Visible;
-                    } else {
-                        *visibility = Visibility::Hidden;
+    // Wind can push the borb away from where it started, so score against where it really is
+    let borb_x = borb_query.single().translation.x;
+    for (children, mut transform, mut interpolated, mut pipe, mut oscillating) in &mut query {
+        let event = sim::scroll_pipe(
+            &mut transform.translation.x,
+            &mut pipe.0,
+            borb_x,
+            modifiers.effects.world_speed,
+            time.delta_seconds(),
+        );
+        match event {
+            PipeEvent::Wrapped => {
+                let obstacle = obstacle_generator.next_obstacle(&modifiers.effects, &mut rng.gameplay);
+                for child in children.iter() {
+                    if let Ok((_, _, mut visibility, pipe_type, _)) = pipe_query.get_mut(*child) {
+                        if pipe_type == &obstacle {
+                            *visibility = Visibility::Visible;
+                        } else {
+                            *visibility = Visibility::Hidden;
+                        }
                     }
                 }
+                let (hole_y, half_gap) = match obstacle {
+                    PipeType::Middle => (0., HALF_PIPE_SPACE),
+                    PipeType::Normal => (
+                        sim::random_pipe_hole_y(&mut rng.gameplay),
+                        sim::random_pipe_half_gap(modifiers.effects.min_half_gap, &mut rng.gameplay),
+                    ),
+                };
+                *oscillating = OscillatingPipe::new(
+                    obstacle,
+                    hole_y,
+                    half_gap,
+                    modifiers.effects.pipe_oscillation,
+                    &mut rng.gameplay,
+                );
             }
-            match obstacle {
-                PipeType::Middle => {
-                    transform.translation.y = 0.;
-                }
-                PipeType::Normal => {
-                    transform.translation.y = random_pipe_hole_y();
+            PipeEvent::Passed => milestones.0.extend(score.add(1)),
+            PipeEvent::Moved => {}
+        }
+
+        let (hole_y, half_gap) = oscillating.step(modifiers.effects.pipe_oscillation, time.delta_seconds());
+        transform.translation.y = hole_y;
+        for child in children.iter() {
+            if let Ok((mut child_transform, mut child_interpolated, _, _, side)) = pipe_query.get_mut(*child) {
+                child_transform.translation.y = match side {
+                    PipeSide::Top => half_gap,
+                    PipeSide::Bottom => -half_gap,
+                    PipeSide::Center => 0.,
+                };
+                if event == PipeEvent::Wrapped {
+                    child_interpolated.teleport(&child_transform);
                 }
             }
-            transform.translation.x = HALF_SCREEN_WIDTH_WITH_HALF_PIPE;
-            pipe.0 = false
-        } else if !pipe.0 && transform.translation.x < BORB_START_POS.x {
-            score.add(1, &mut upgrades, &mut chaos, &mut commands);
-            pipe.0 = true;
+        }
+        if event == PipeEvent::Wrapped {
+            interpolated.teleport(&transform);
         }
     }
 }
 
+fn read_input(
+    action_input: ActionInput,
+    mouse_input: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    button_query: Query<&Interaction, With<Button>>,
+    time: Res<Time<Real>>,
+    // Time since the last one finger tap
+    mut last_tap: Local<Option<f32>>,
+    mut input: ResMut<BorbInput>,
+) {
+    for action in [Action::Flap, Action::FastFall, Action::Hover, Action::BulletTime] {
+        if action_input.just_pressed(action) {
+            input.press(action);
+        }
+    }
+    input.glide = action_input.pressed(Action::Glide);
+
+    if let Some(since) = last_tap.as_mut() {
+        *since += time.delta_seconds();
+    }
+    // Clicks and taps that land on a button are for the button
+    if button_query.iter().all(|interaction| *interaction == Interaction::None) {
+        if mouse_input.just_pressed(MouseButton::Left) {
+            input.press(Action::Flap);
+        }
+        if mouse_input.just_pressed(MouseButton::Right) {
+            input.press(Action::FastFall);
+        }
+        if touches.any_just_pressed() {
+            // Taps flap right away, a second finger landing with the first turns the flap into a dive
+            let together = touches.iter_just_pressed().count() >= 2
+                || last_tap.is_some_and(|since| since <= TWO_FINGER_TAP_WINDOW);
+            if together && touches.iter().count() >= 2 {
+                *last_tap = None;
+                input.press(Action::FastFall);
+            } else {
+                *last_tap = Some(0.);
+                input.press(Action::Flap);
+            }
+        }
+    }
+}
+
+// Runs on the unscaled tick so bullet time lasts as long as it says
+fn update_time_scale(
+    mut input: ResMut<BorbInput>,
+    mut bullet_time: ResMut<BulletTime>,
+    mut time_scale: ResMut<TimeScale>,
+    modifiers: Res<Modifiers>,
+    time: Res<Time>,
+) {
+    let bullet_time_pressed = std::mem::take(&mut input.bullet_time);
+    if let Some(duration) = modifiers.effects.bullet_time_duration.filter(|_| bullet_time_pressed) {
+        bullet_time.trigger(duration);
+    }
+    time_scale.0 = bullet_time.step(time.delta_seconds());
+}
+
 fn jump(
     mut commands: Commands,
-    mut query: Query<(&mut Gravity, &Transform), With<Borb>>,
-    keyboard_input: Res<Input<KeyCode>>,
-    upgrades: Res<BorbUpgrades>,
-    asset_server: Res<AssetServer>,
+    mut query: Query<(&mut Gravity, &Transform, &BorbSize), With<Borb>>,
+    mut input: ResMut<BorbInput>,
+    mut darkness: ResMut<Darkness>,
+    modifiers: Res<Modifiers>,
+    assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
 ) {
-    let (mut gravity, transform) = query.single_mut();
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        gravity.gravity = JUMP_FORCE;
-        gravity.from_fast_fall = false;
+    let (mut gravity, transform, size) = query.single_mut();
+    let jump_pressed = std::mem::take(&mut input.jump);
+    let fast_fall_pressed = std::mem::take(&mut input.fast_fall);
+    // Particles come out on the other side while gravity is inverted
+    let mirror = if gravity.inverted { Vec2::new(1., -1.) } else { Vec2::ONE };
+    if jump_pressed {
+        gravity.jump();
+        darkness.flash();
         for i in 0..JUMP_PARTICLE_NUM {
             create_particle(
-                transform.translation.truncate() + JUMP_PARTICLE_RELATIVE_START_POS + JUMP_PARTICLE_DISTANCE * i as f32,
-                JUMP_PARTICLE_DIRECTION,
+                transform.translation.truncate() + (JUMP_PARTICLE_RELATIVE_START_POS + JUMP_PARTICLE_DISTANCE * i as f32) * mirror * size.0,
+                JUMP_PARTICLE_DIRECTION * mirror,
                 &mut commands,
-                &asset_server
+                &assets,
+                &mut rng,
             );
         }
-    } else if upgrades.fast_fall && keyboard_input.just_pressed(KeyCode::ControlLeft) {
-        gravity.gravity = FAST_FALL_SPEED;
-        gravity.from_fast_fall = true;
+    } else if let Some(speed) = modifiers.effects.fast_fall_speed.filter(|_| fast_fall_pressed) {
+        gravity.fast_fall(speed);
         for i in 0..JUMP_PARTICLE_NUM {
             create_particle(
-                transform.translation.truncate() + FAST_FALL_PARTICLE_RELATIVE_START_POS + JUMP_PARTICLE_DISTANCE * i as f32,
-                FAST_FALL_PARTICLE_DIRECTION,
+                transform.translation.truncate() + (FAST_FALL_PARTICLE_RELATIVE_START_POS + JUMP_PARTICLE_DISTANCE * i as f32) * mirror * size.0,
+                FAST_FALL_PARTICLE_DIRECTION * mirror,
                 &mut commands,
-                &asset_server
+                &assets,
+                &mut rng,
+            );
+        }
+    }
+}
+
+fn flip_gravity(
+    mut query: Query<&mut Gravity, With<Borb>>,
+    mut gravity_flip: ResMut<GravityFlip>,
+    modifiers: Res<Modifiers>,
+    time: GameTime,
+) {
+    let mut gravity = query.single_mut();
+    gravity_flip.step(&mut gravity, modifiers.effects.gravity_flip_interval, time.delta_seconds());
+}
+
+// Sits between the actions that were pressed and the borb, replays keep what was pressed
+fn scramble_controls(
+    mut commands: Commands,
+    mut input: ResMut<BorbInput>,
+    mut control_scramble: ResMut<ControlScramble>,
+    modifiers: Res<Modifiers>,
+    mut rng: ResMut<GameRng>,
+    time: GameTime,
+) {
+    let scrambling = modifiers.effects.scrambling;
+    match control_scramble.step(scrambling, &mut rng.gameplay, time.delta_seconds()) {
+        Some(Scramble::Reversed) => create_notification("controls reversed", &mut commands),
+        Some(Scramble::Delayed) => create_notification("controls delayed", &mut commands),
+        None => {}
+    }
+    (input.jump, input.fast_fall) = control_scramble.route(input.jump, input.fast_fall, scrambling, time.delta_seconds());
+}
+
+#[allow(clippy::too_many_arguments)]
+fn glide_and_hover(
+    mut commands: Commands,
+    mut query: Query<(&mut Gravity, &mut Glide, &mut Hover, &Transform, &BorbSize), With<Borb>>,
+    mut input: ResMut<BorbInput>,
+    modifiers: Res<Modifiers>,
+    assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
+    tick: Res<SimulationTick>,
+    time: GameTime,
+) {
+    let (mut gravity, mut glide, mut hover, transform, size) = query.single_mut();
+    let hover_pressed = std::mem::take(&mut input.hover);
+    if let Some(cooldown) = modifiers.effects.hover_cooldown.filter(|_| hover_pressed) {
+        if hover.trigger(&mut gravity, cooldown) {
+            for i in 0..HOVER_PARTICLE_NUM {
+                let spread = HOVER_PARTICLE_SPREAD * i as f32;
+                for direction in [Vec2::new(-1., -spread), Vec2::new(1., -spread)] {
+                    create_particle(transform.translation.truncate(), direction.normalize(), &mut commands, &assets, &mut rng);
+                }
+            }
+        }
+    }
+    hover.step(&mut gravity, time.delta_seconds());
+
+    if let Some(max_stamina) = modifiers.effects.glide_stamina {
+        glide.step(&mut gravity, input.glide, max_stamina, time.delta_seconds());
+        if glide.gliding && tick.0.is_multiple_of(GLIDE_PARTICLE_INTERVAL) {
+            create_particle(
+                transform.translation.truncate() + GLIDE_PARTICLE_RELATIVE_START_POS * size.0,
+                GLIDE_PARTICLE_DIRECTION,
+                &mut commands,
+                &assets,
+                &mut rng,
             );
         }
     }
@@ -479,75 +1007,167 @@ fn jump(
 
 fn apply_gravity(
     mut query: Query<(&mut Transform, &mut Gravity)>,
-    time: Res<Time>,
+    time: GameTime,
 ) {
     for (mut transform, mut gravity) in &mut query {
-        gravity.gravity -= GRAVITY * time.delta_seconds();
-        if gravity.from_fast_fall {
-            gravity.gravity = gravity.gravity.max(FAST_FALL_SPEED);
-        } else {
-            gravity.gravity = gravity.gravity.max(MAX_GRAVITY);
+        transform.translation.y += gravity.step(time.delta_seconds());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn blow_wind(
+    mut commands: Commands,
+    mut borb_query: Query<&mut Transform, With<Borb>>,
+    mut wind: ResMut<Wind>,
+    modifiers: Res<Modifiers>,
+    assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
+    tick: Res<SimulationTick>,
+    time: GameTime,
+) {
+    wind.step(modifiers.effects.wind_strength, &mut rng.gameplay, time.delta_seconds());
+    let (wind_x, wind_y) = wind.velocity();
+    let mut transform = borb_query.single_mut();
+    transform.translation.x = sim::drift(transform.translation.x, BORB_START_POS.x, wind_x, time.delta_seconds());
+    transform.translation.y += wind_y * time.delta_seconds();
+
+    // Streaks across the screen show the gust coming before it hits
+    if wind.phase != WindPhase::Calm && tick.0.is_multiple_of(WIND_STREAK_INTERVAL) {
+        let position = Vec2::new(
+            (rng.cosmetic.gen::<f32>() - 0.5) * SCREEN_WIDTH,
+            (rng.cosmetic.gen::<f32>() - 0.5) * HALF_SCREEN_HEIGHT * 2.,
+        );
+        create_particle(position, Vec2::from(wind.direction), &mut commands, &assets, &mut rng);
+    }
+}
+
+fn resize_borb(
+    mut query: Query<(&mut Transform, &mut Collider, &mut BorbSize), With<Borb>>,
+    modifiers: Res<Modifiers>,
+    time: GameTime,
+) {
+    for (mut transform, mut collider, mut size) in &mut query {
+        if size.0 == modifiers.effects.borb_size {
+            continue;
         }
-        transform.translation.y += gravity.gravity * time.delta_seconds();
+        size.0 = sim::tween_size(size.0, modifiers.effects.borb_size, time.delta_seconds());
+        transform.scale = SCALE * size.0;
+        collider.0 = BORB_COLLIDER * size.0;
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn check_for_collisions(
     mut commands: Commands,
-    borb_query: Query<(&Transform, &Collider), With<Borb>>,
-    collider_query: Query<(&GlobalTransform, &Collider, &Obstacle, &Visibility)>,
+    borb_query: Query<(Entity, &Transform, &Collider, Option<&Invulnerable>), With<Borb>>,
+    collider_query: Query<(&Transform, Option<&Parent>, &Collider, &Obstacle, &Visibility), Without<Borb>>,
+    parent_query: Query<&Transform, Without<Obstacle>>,
+    mut run_over: ResMut<GameOver>,
     mut game_state: ResMut<NextState<GameState>>,
     mut menu_state: ResMut<NextState<MenuState>>,
-    sound: Res<CollisionSound>,
+    mut game_over_events: EventWriter<GameOverEvent>,
+    mut modifiers: ResMut<Modifiers>,
+    assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
+    time_scale: Res<TimeScale>,
 ) {
-    let (borb_transform, borb_collider) = borb_query.single();
-
-    for (transform, collider, enemy, visible) in &collider_query {
-        if visible == &Visibility::Visible {
-            let collision = collide(
-                borb_transform.translation,
-                borb_collider.0,
-                transform.translation(),
-                collider.0,
-            );
-            if let Some(_) = collision {
-                game_over(enemy.0, &mut game_state, &mut menu_state);
+    let (borb, borb_transform, borb_collider, invulnerable) = borb_query.single();
+    if invulnerable.is_some() {
+        return;
+    }
+    let borb_box = collider_box(borb_transform.translation, borb_collider);
+
+    for (transform, parent, collider, enemy, visible) in &collider_query {
+        if visible == Visibility::Visible {
+            // GlobalTransform holds the interpolated render position, so work out the simulated one
+            let translation = match parent.and_then(|parent| parent_query.get(parent.get()).ok()) {
+                Some(parent_transform) => parent_transform.transform_point(transform.translation),
+                None => transform.translation,
+            };
+            if borb_box.overlaps(&collider_box(translation, collider)) {
+                if modifiers.absorb_hit() {
+                    commands.entity(borb).insert(Invulnerable(INVULNERABILITY_TIME));
+                    for i in 0..SHIELD_PARTICLE_NUM {
+                        let angle = 2. * PI * i as f32 / SHIELD_PARTICLE_NUM as f32;
+                        create_particle(
+                            borb_transform.translation.truncate(),
+                            Vec2::from_angle(angle),
+                            &mut commands,
+                            &assets,
+                            &mut rng,
+                        );
+                    }
+                    return;
+                }
+                game_over(enemy.0, &mut run_over, &mut game_state, &mut menu_state, &mut game_over_events);
                 commands.spawn(AudioBundle {
-                    source: sound.0.clone(),
-                    settings: PlaybackSettings::DESPAWN,
+                    source: assets.game_over_sound.clone(),
+                    settings: PlaybackSettings::DESPAWN.with_speed(time_scale.0),
                 });
+                return;
             }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn check_out_of_bounds(
     mut commands: Commands,
-    mut borb_query: Query<&Transform, With<Borb>>,
+    borb_query: Query<(&Transform, &BorbSize), With<Borb>>,
+    mut run_over: ResMut<GameOver>,
     mut game_state: ResMut<NextState<GameState>>,
     mut menu_state: ResMut<NextState<MenuState>>,
-    sound: Res<CollisionSound>,
+    mut game_over_events: EventWriter<GameOverEvent>,
+    assets: Res<GameAssets>,
+    time_scale: Res<TimeScale>,
 ) {
-    let borb_transform = borb_query.single_mut();
-    let y = borb_transform.translation.y;
-    if y < -HALF_SCREEN_SIZE.y - BORB_HALF_HEIGHT {
-        game_over(GameOverCause::TooLow, &mut game_state, &mut menu_state);
-        commands.spawn(AudioBundle {
-            source: sound.0.clone(),
-            settings: PlaybackSettings::DESPAWN,
-        });
-    } else if y > HALF_SCREEN_SIZE.y + BORB_HALF_HEIGHT {
-        game_over(GameOverCause::TooHigh, &mut game_state, &mut menu_state);
+    // A pipe may already have ended the run this tick
+    if run_over.0.is_some() {
+        return;
+    }
+    let (borb_transform, size) = borb_query.single();
+    if let Some(cause) = sim::out_of_bounds(borb_transform.translation.y, BORB_HALF_HEIGHT * size.0) {
+        game_over(cause, &mut run_over, &mut game_state, &mut menu_state, &mut game_over_events);
         commands.spawn(AudioBundle {
-            source: sound.0.clone(),
-            settings: PlaybackSettings::DESPAWN,
+            source: assets.game_over_sound.clone(),
+            settings: PlaybackSettings::DESPAWN.with_speed(time_scale.0),
         });
     }
 }
 
+// Runs after the collision checks, so a run that ends this tick is not offered anything
+fn reach_milestones(
+    mut commands: Commands,
+    mut milestones: ResMut<Milestones>,
+    run_over: Res<GameOver>,
+    mut modifiers: ResMut<Modifiers>,
+    mut rng: ResMut<GameRng>,
+) {
+    if run_over.0.is_some() {
+        milestones.0.clear();
+        return;
+    }
+    for milestone in milestones.0.drain(..) {
+        match milestone {
+            ModifierKind::Chaos => {
+                if let Some(chaos) = modifiers.grant(ModifierKind::Chaos, &mut rng.gameplay) {
+                    create_notification(&chaos.notification_at(modifiers.level(chaos.id)), &mut commands);
+                }
+            }
+            ModifierKind::Upgrade => {
+                let options = modifiers.offer(ModifierKind::Upgrade, UPGRADE_CHOICES, &mut rng.gameplay);
+                if !options.is_empty() {
+                    let upcoming_chaos = modifiers.upcoming_chaos(&mut rng.gameplay);
+                    commands.insert_resource(UpgradeChoice { options, upcoming_chaos });
+                }
+            }
+        }
+    }
+}
+
 fn update_notifications(
     mut text_query: Query<(Entity, &mut Style, &mut Text, &mut Notification)>,
-    time: Res<Time>,
+    time: GameTime,
     mut commands: Commands,
 ) {
     for (entity, mut style, mut text, mut notif) in &mut text_query {
@@ -563,23 +1183,256 @@ fn update_notifications(
 }
 
 fn update_borb_rotation(
-    mut borb_query: Query<(&mut Transform, &Gravity), With<Borb>>,
+    mut borb_query: Query<(&mut Transform, &mut Sprite, &Gravity), With<Borb>>,
+) {
+    let (mut transform, mut sprite, gravity) = borb_query.single_mut();
+    // Upside down the borb's own idea of up is the world's down
+    let rotation = if gravity.inverted { -gravity.gravity } else { gravity.gravity };
+    transform.rotation = Quat::from_rotation_z(rotation * DEGREES_PER_GRAVITY);
+    sprite.flip_y = gravity.inverted;
+}
+
+#[allow(clippy::type_complexity)]
+fn update_darkness(
+    mut overlay_query: Query<(&mut Transform, &mut Visibility, &Children), With<DarknessOverlay>>,
+    mut part_query: Query<(&mut Transform, &mut Sprite, &DarknessPart), Without<DarknessOverlay>>,
+    borb_query: Query<&Transform, (With<Borb>, Without<DarknessOverlay>, Without<DarknessPart>)>,
+    mut darkness: ResMut<Darkness>,
+    modifiers: Res<Modifiers>,
+    time: GameTime,
+) {
+    let borb_transform = borb_query.single();
+    let (mut transform, mut visibility, children) = overlay_query.single_mut();
+    let Some(radius) = modifiers.effects.darkness_radius else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    let radius = darkness.step(radius, time.delta_seconds());
+    visibility.set_if_neq(Visibility::Inherited);
+    transform.translation.x = borb_transform.translation.x;
+    transform.translation.y = borb_transform.translation.y;
+
+    let edge = radius + DARKNESS_COVER / 2.;
+    for child in children.iter() {
+        let Ok((mut part_transform, mut sprite, part)) = part_query.get_mut(*child) else {
+            continue;
+        };
+        let (position, size) = match part {
+            DarknessPart::Hole => (Vec2::ZERO, Vec2::splat(radius * 2.)),
+            DarknessPart::Left => (Vec2::new(-edge, 0.), Vec2::new(DARKNESS_COVER, DARKNESS_COVER * 2.)),
+            DarknessPart::Right => (Vec2::new(edge, 0.), Vec2::new(DARKNESS_COVER, DARKNESS_COVER * 2.)),
+            DarknessPart::Top => (Vec2::new(0., edge), Vec2::new(radius * 2., DARKNESS_COVER)),
+            DarknessPart::Bottom => (Vec2::new(0., -edge), Vec2::new(radius * 2., DARKNESS_COVER)),
+        };
+        part_transform.translation = position.extend(0.);
+        sprite.custom_size = Some(size);
+    }
+}
+
+fn toggle_pause(
+    mut commands: Commands,
+    action_input: ActionInput,
+    paused: Option<Res<Paused>>,
+    pause_screen_query: Query<Entity, With<OnPauseScreen>>,
+    mut input: ResMut<BorbInput>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if !action_input.just_pressed(Action::Pause) {
+        return;
+    }
+    if paused.is_some() {
+        commands.remove_resource::<Paused>();
+        time.unpause();
+        // Presses made while paused are not for the borb
+        *input = BorbInput::default();
+        for entity in &pause_screen_query {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    commands.insert_resource(Paused);
+    time.pause();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: PAUSE_BACKGROUND.into(),
+                ..default()
+            },
+            OnPauseScreen,
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font_size: PAUSE_TEXT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn clear_pause(mut commands: Commands, mut time: ResMut<Time<Virtual>>) {
+    commands.remove_resource::<Paused>();
+    time.unpause();
+}
+
+fn clear_upgrade_choice(mut commands: Commands) {
+    commands.remove_resource::<UpgradeChoice>();
+}
+
+fn still_running(run_over: Res<GameOver>) -> bool {
+    run_over.0.is_none()
+}
+
+fn pause_for_upgrade_choice(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn apply_upgrade_choice(
+    mut commands: Commands,
+    mut choose_events: EventReader<ChooseUpgrade>,
+    choice: Res<UpgradeChoice>,
+    mut modifiers: ResMut<Modifiers>,
+    mut input: ResMut<BorbInput>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let Some(&upgrade) = choose_events.read().find_map(|event| choice.options.get(event.0)) else {
+        return;
+    };
+    choose_events.clear();
+    modifiers.take(upgrade);
+    create_notification(&upgrade.notification_at(modifiers.level(upgrade.id)), &mut commands);
+    // The key that picked the upgrade should not also flap once the game goes on
+    *input = BorbInput::default();
+    commands.remove_resource::<UpgradeChoice>();
+    time.unpause();
+}
+
+fn update_invulnerability(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Invulnerable)>,
+    time: GameTime,
+) {
+    for (entity, mut invulnerable) in &mut query {
+        invulnerable.0 -= time.delta_seconds();
+        if invulnerable.0 <= 0. {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+fn blink_invulnerable_borb(mut query: Query<(&mut Visibility, Option<&Invulnerable>), With<Borb>>) {
+    for (mut visibility, invulnerable) in &mut query {
+        let hidden = invulnerable.is_some_and(|invulnerable| (invulnerable.0 / INVULNERABILITY_BLINK_TIME) as u32 % 2 == 1);
+        visibility.set_if_neq(if hidden { Visibility::Hidden } else { Visibility::Inherited });
+    }
+}
+
+fn update_shield_icon(
+    mut query: Query<&mut Visibility, With<ShieldIcon>>,
+    modifiers: Res<Modifiers>,
+) {
+    for mut visibility in &mut query {
+        visibility.set_if_neq(if modifiers.effects.shield { Visibility::Inherited } else { Visibility::Hidden });
+    }
+}
+
+fn update_bullet_time_effects(
+    mut tint_query: Query<&mut Visibility, With<BulletTimeTint>>,
+    sink_query: Query<&AudioSink>,
+    time_scale: Res<TimeScale>,
+) {
+    let active = time_scale.0 < 1.;
+    for mut visibility in &mut tint_query {
+        visibility.set_if_neq(if active { Visibility::Inherited } else { Visibility::Hidden });
+    }
+    // Playback speed also lowers the pitch
+    for sink in &sink_query {
+        sink.set_speed(time_scale.0);
+    }
+}
+
+fn update_wind_arrow(
+    mut arrow_query: Query<(&mut Transform, &mut Visibility), With<WindArrow>>,
+    wind: Res<Wind>,
+) {
+    for (mut transform, mut visibility) in &mut arrow_query {
+        let visible = match wind.phase {
+            WindPhase::Calm => false,
+            WindPhase::Warning => ((wind.timer / WIND_ARROW_BLINK_TIME) as u32).is_multiple_of(2),
+            WindPhase::Gust => true,
+        };
+        visibility.set_if_neq(if visible { Visibility::Inherited } else { Visibility::Hidden });
+        transform.rotation = Quat::from_rotation_z(wind.direction.1.atan2(wind.direction.0));
+    }
+}
+
+fn update_gravity_countdown(
+    mut text_query: Query<(&mut Text, &mut Visibility), With<GravityCountdown>>,
+    gravity_flip: Res<GravityFlip>,
+) {
+    for (mut text, mut visibility) in &mut text_query {
+        let Some(seconds) = gravity_flip.warning() else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Inherited);
+        text.sections[0].value = format!("gravity flips in {seconds}");
+    }
+}
+
+fn update_stamina_bar(
+    mut bar_query: Query<(&mut Visibility, &Children), With<StaminaBar>>,
+    mut fill_query: Query<&mut Style>,
+    glide_query: Query<&Glide, With<Borb>>,
+    modifiers: Res<Modifiers>,
 ) {
-    let (mut transform, gravity) = borb_query.single_mut();
-    transform.rotation = Quat::from_rotation_z(gravity.gravity * DEGREES_PER_GRAVITY);
+    let Ok(glide) = glide_query.get_single() else {
+        return;
+    };
+    for (mut visibility, children) in &mut bar_query {
+        let Some(max_stamina) = modifiers.effects.glide_stamina else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Inherited);
+        for child in children.iter() {
+            if let Ok(mut style) = fill_query.get_mut(*child) {
+                style.width = Val::Percent(glide.stamina / max_stamina * 100.);
+            }
+        }
+    }
+}
+
+fn advance_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
 }
 
 fn update_particles(
     mut commands: Commands,
     mut query: Query<(Entity, &mut Transform, &mut Sprite, &mut Particle)>,
-    time: Res<Time>,
-    chaos: Res<WorldChaos>,
+    time: GameTime,
+    modifiers: Res<Modifiers>,
+    wind: Res<Wind>,
 ) {
+    let (wind_x, wind_y) = wind.velocity();
     for (entity, mut transform, mut sprite, mut particle) in &mut query {
         particle.speed -= PARTICLE_DECEL * time.delta_seconds();
         particle.speed = particle.speed.max(0.);
         transform.translation += particle.direction * particle.speed * time.delta_seconds();
-        transform.translation.x -= chaos.world_speed * time.delta_seconds();
+        transform.translation.x -= modifiers.effects.world_speed * time.delta_seconds();
+        transform.translation += Vec3::new(wind_x, wind_y, 0.) * time.delta_seconds();
         transform.rotate_z(particle.speed * particle.rotation_speed * time.delta_seconds());
         let alpha = sprite.color.a() - PARTICLE_ALPHA_SPEED * time.delta_seconds();
         if alpha <= 0.0 {
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use crate::{GameState, SCALE, TEXT_COLOR};
+use crate::game::{GameAssets, OnGameScreen, SimulationTick, BORB_START_POS, DEGREES_PER_GRAVITY, TICKS_PER_SECOND};
+use crate::interpolation::{Interpolated, SimulationSet};
+use crate::replay::Replay;
+use crate::sim::Scoreboard;
+
+const GHOST_ALPHA: f32 = 0.4;
+const GHOST_FADE_SPEED: f32 = 0.8;
+const GHOST_Z: f32 = -0.1;
+
+const GHOST_GAP_TEXT_SIZE: f32 = 25.;
+const GHOST_GAP_TEXT_MARGIN: f32 = 10.;
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(GameState::Game), spawn_ghost.run_if(resource_exists::<GhostRace>()))
+            .add_systems(FixedUpdate, move_ghost
+                .in_set(SimulationSet::Simulate)
+                .run_if(in_state(GameState::Game).and_then(resource_exists::<GhostRace>())))
+            .add_systems(Update, update_ghost_gap
+                .run_if(in_state(GameState::Game).and_then(resource_exists::<GhostRace>())))
+            .add_systems(OnExit(GameState::Game), stop_race);
+    }
+}
+
+// The replay the ghost follows. The run is played on its seed, so both see the same pipes until
+// the player picks a different upgrade and the shared gameplay rng goes its own way
+#[derive(Resource)]
+pub struct GhostRace(pub Replay);
+
+#[derive(Component)]
+struct Ghost;
+
+#[derive(Component)]
+struct GhostGapText;
+
+fn spawn_ghost(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+) {
+    let transform = Transform::from_translation(BORB_START_POS.truncate().extend(GHOST_Z)).with_scale(SCALE);
+    commands.spawn((
+        SpriteBundle {
+            texture: assets.borb.clone(),
+            transform,
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, GHOST_ALPHA),
+                ..default()
+            },
+            ..default()
+        },
+        Interpolated::new(&transform),
+        Ghost,
+        OnGameScreen,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: GHOST_GAP_TEXT_SIZE,
+                color: TEXT_COLOR,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(GHOST_GAP_TEXT_MARGIN),
+            right: Val::Px(GHOST_GAP_TEXT_MARGIN),
+            ..default()
+        }),
+        GhostGapText,
+        OnGameScreen,
+    ));
+}
+
+fn move_ghost(
+    mut commands: Commands,
+    mut ghost_query: Query<(Entity, &mut Transform, &mut Sprite), With<Ghost>>,
+    race: Res<GhostRace>,
+    tick: Res<SimulationTick>,
+    time: Res<Time>,
+) {
+    let Ok((entity, mut transform, mut sprite)) = ghost_query.get_single_mut() else {
+        return;
+    };
+    let flight = &race.0.flight;
+    let index = tick.0.saturating_sub(1) as usize;
+    if let Some(&point) = flight.get(index) {
+        let previous_y = index.checked_sub(1).map_or(point.y, |previous| flight[previous].y);
+        let velocity = (point.y - previous_y) * TICKS_PER_SECOND as f32;
+        transform.translation.x = point.x;
+        transform.translation.y = point.y;
+        // The world velocity tilts the borb the same way whichever way gravity pulls
+        transform.rotation = Quat::from_rotation_z(velocity * DEGREES_PER_GRAVITY);
+        sprite.flip_y = point.inverted;
+    } else {
+        // The ghost's run has ended
+        let alpha = sprite.color.a() - GHOST_FADE_SPEED * time.delta_seconds();
+        if alpha <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            sprite.color.set_a(alpha);
+        }
+    }
+}
+
+fn update_ghost_gap(
+    mut text_query: Query<&mut Text, With<GhostGapText>>,
+    race: Res<GhostRace>,
+    tick: Res<SimulationTick>,
+    score: Res<Scoreboard>,
+) {
+    let ghost_score = race.0.score_ticks.partition_point(|&scored| scored <= tick.0);
+    let gap = score.score as i64 - ghost_score as i64;
+    for mut text in &mut text_query {
+        text.sections[0].value = format!("ghost {gap:+}");
+    }
+}
+
+fn stop_race(mut commands: Commands) {
+    commands.remove_resource::<GhostRace>();
+}
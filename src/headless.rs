@@ -0,0 +1,121 @@
+use bevy::{input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+use std::{path::PathBuf, time::Duration};
+use crate::{GameOverCause, GameState, MenuState};
+use crate::game::{GamePlugin, GameRng, NextSeed, SimulationTick, TICKS_PER_SECOND};
+use crate::interpolation::InterpolationPlugin;
+use crate::replay::{Replay, ReplayPlayback, ReplayPlugin};
+use crate::sim::Scoreboard;
+
+// Ten minutes of play
+const DEFAULT_MAX_TICKS: u32 = TICKS_PER_SECOND as u32 * 60 * 10;
+
+// Settings for a run without a window, read from the command line by `from_args`
+#[derive(Default)]
+pub struct HeadlessOptions {
+    pub seed: Option<u64>,
+    pub inputs: Option<Replay>,
+    pub max_ticks: Option<u32>,
+}
+
+impl HeadlessOptions {
+    // Understands `--seed <number>`, `--replay <file>` and `--max-ticks <number>`
+    pub fn from_args() -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => options.seed = Some(parse_value(&arg, args.next())?),
+                "--max-ticks" => options.max_ticks = Some(parse_value(&arg, args.next())?),
+                "--replay" => {
+                    let path = PathBuf::from(args.next().ok_or("--replay needs a file")?);
+                    let replay = Replay::load(&path)
+                        .map_err(|error| format!("could not load {}: {error}", path.display()))?;
+                    options.inputs = Some(replay);
+                }
+                _ => {}
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: Option<String>) -> Result<T, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("{arg} needs a number"))
+}
+
+pub struct HeadlessOutcome {
+    pub seed: u64,
+    pub score: u32,
+    pub ticks: u32,
+    // None when the run was still going after the tick limit
+    pub cause: Option<GameOverCause>,
+}
+
+// Builds the game without rendering, audio or menus, one update is exactly one simulation tick
+pub fn build_app(options: &HeadlessOptions) -> App {
+    let inputs = options.inputs.clone().unwrap_or_default();
+    let seed = options.seed.or(options.inputs.as_ref().map(|replay| replay.seed));
+
+    let mut app = App::new();
+    app
+        .add_plugins((MinimalPlugins, InputPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / TICKS_PER_SECOND)))
+        .add_state::<GameState>()
+        .add_state::<MenuState>()
+        .insert_resource(NextSeed(seed))
+        // Playing back, even an empty script, also keeps headless runs from overwriting the saved replays
+        .insert_resource(ReplayPlayback::new(inputs))
+        .add_plugins((InterpolationPlugin, GamePlugin, ReplayPlugin))
+        .add_systems(Startup, start_game);
+    app.finish();
+    app.cleanup();
+    app
+}
+
+pub fn simulate(options: &HeadlessOptions) -> HeadlessOutcome {
+    let mut app = build_app(options);
+    let max_ticks = options.max_ticks.unwrap_or(DEFAULT_MAX_TICKS);
+    let mut started = false;
+    loop {
+        app.update();
+        let in_game = *app.world.resource::<State<GameState>>().get() == GameState::Game;
+        started |= in_game;
+        let ticks = app.world.get_resource::<SimulationTick>().map_or(0, |tick| tick.0);
+        if (started && !in_game) || ticks >= max_ticks {
+            let cause = match app.world.resource::<State<MenuState>>().get() {
+                MenuState::GameOver(cause) if !in_game => Some(*cause),
+                _ => None,
+            };
+            return HeadlessOutcome {
+                seed: app.world.resource::<GameRng>().seed,
+                score: app.world.resource::<Scoreboard>().score,
+                ticks,
+                cause,
+            };
+        }
+    }
+}
+
+pub fn run() {
+    let options = match HeadlessOptions::from_args() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(2);
+        }
+    };
+    let outcome = simulate(&options);
+    println!("seed: {}", outcome.seed);
+    println!("score: {}", outcome.score);
+    println!("ticks: {}", outcome.ticks);
+    match outcome.cause {
+        Some(cause) => println!("cause: {cause:?}"),
+        None => println!("cause: none, tick limit reached"),
+    }
+}
+
+fn start_game(mut game_state: ResMut<NextState<GameState>>) {
+    game_state.set(GameState::Game);
+}
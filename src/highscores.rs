@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+use crate::{data_dir, GameOverCause, GameState};
+use crate::game::{GameMode, GameOverEvent, GameRng};
+use crate::replay::ReplayPlayback;
+use crate::sim::Scoreboard;
+
+pub const MAX_HIGH_SCORES: usize = 5;
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        let high_scores = match HighScores::load(&high_scores_path()) {
+            Ok(high_scores) => high_scores,
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    warn!("could not load high scores: {error}");
+                }
+                HighScores::default()
+            }
+        };
+        app
+            .insert_resource(high_scores)
+            .init_resource::<LastRun>()
+            .add_systems(OnEnter(GameState::Game), reset_last_run)
+            .add_systems(Update, record_high_score
+                .run_if(in_state(GameState::Game).and_then(not(resource_exists::<ReplayPlayback>()))));
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScoreEntry {
+    pub score: u32,
+    pub date: String,
+    pub seed: u64,
+    pub cause: GameOverCause,
+    pub mode: GameMode,
+}
+
+// Best runs first, never longer than MAX_HIGH_SCORES
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = ron::ser::to_string_pretty(self, default())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::write(path, text)
+    }
+
+    pub fn best(&self) -> Option<u32> {
+        self.entries.first().map(|entry| entry.score)
+    }
+
+    // Returns where the entry landed, or None if it did not make the table.
+    // Ties go below older entries so the first to reach a score keeps its place.
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        let rank = self.entries.partition_point(|existing| existing.score >= entry.score);
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
+
+pub fn high_scores_path() -> PathBuf {
+    data_dir().join("highscores.ron")
+}
+
+// How the run that just ended did against the table, read by the game over menu
+#[derive(Resource, Default)]
+pub struct LastRun {
+    pub rank: Option<usize>,
+    pub new_best: bool,
+}
+
+// Formats the current UTC date as YYYY-MM-DD
+pub fn today() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// Converts days since 1970-01-01 to a proleptic Gregorian date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// systems
+fn reset_last_run(mut last_run: ResMut<LastRun>) {
+    *last_run = LastRun::default();
+}
+
+fn record_high_score(
+    mut game_over_events: EventReader<GameOverEvent>,
+    mut last_run: ResMut<LastRun>,
+    mut high_scores: ResMut<HighScores>,
+    score: Res<Scoreboard>,
+    rng: Res<GameRng>,
+    mode: Res<GameMode>,
+) {
+    let Some(cause) = game_over_events.read().next().map(|event| event.0) else {
+        return;
+    };
+    if score.score == 0 {
+        return;
+    }
+
+    let previous_best = high_scores.best();
+    last_run.new_best = previous_best.is_none_or(|best| score.score > best);
+    last_run.rank = high_scores.insert(HighScoreEntry {
+        score: score.score,
+        date: today(),
+        seed: rng.seed,
+        cause,
+        mode: *mode,
+    });
+    if last_run.rank.is_some() {
+        if let Err(error) = high_scores.save(&high_scores_path()) {
+            warn!("could not save high scores: {error}");
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .configure_sets(FixedUpdate, (
+                SimulationSet::Restore,
+                SimulationSet::Simulate,
+                SimulationSet::Capture,
+            ).chain())
+            .add_systems(FixedUpdate, (
+                restore_simulated_transforms.in_set(SimulationSet::Restore),
+                capture_simulated_transforms.in_set(SimulationSet::Capture),
+            ))
+            .add_systems(
+                PostUpdate,
+                interpolate_transforms.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+// Every simulation tick runs Restore -> Simulate -> Capture, gameplay goes in Simulate
+#[derive(SystemSet, Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum SimulationSet {
+    Restore,
+    Simulate,
+    Capture,
+}
+
+// Keeps the last two simulated states of an entity so rendering can blend between them
+#[derive(Component)]
+pub struct Interpolated {
+    previous_translation: Vec3,
+    previous_rotation: Quat,
+    current_translation: Vec3,
+    current_rotation: Quat,
+}
+
+impl Interpolated {
+    pub fn new(transform: &Transform) -> Self {
+        Self {
+            previous_translation: transform.translation,
+            previous_rotation: transform.rotation,
+            current_translation: transform.translation,
+            current_rotation: transform.rotation,
+        }
+    }
+
+    // Call after moving an entity somewhere it should not be blended towards, like a pipe wrapping around
+    pub fn teleport(&mut self, transform: &Transform) {
+        self.previous_translation = transform.translation;
+        self.previous_rotation = transform.rotation;
+    }
+}
+
+fn restore_simulated_transforms(mut query: Query<(&mut Transform, &mut Interpolated)>) {
+    for (mut transform, mut interpolated) in &mut query {
+        transform.translation = interpolated.current_translation;
+        transform.rotation = interpolated.current_rotation;
+        interpolated.previous_translation = interpolated.current_translation;
+        interpolated.previous_rotation = interpolated.current_rotation;
+    }
+}
+
+fn capture_simulated_transforms(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in &mut query {
+        interpolated.current_translation = transform.translation;
+        interpolated.current_rotation = transform.rotation;
+    }
+}
+
+fn interpolate_transforms(
+    mut query: Query<(&mut Transform, &Interpolated)>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    let alpha = fixed_time.overstep_percentage();
+    for (mut transform, interpolated) in &mut query {
+        transform.translation = interpolated.previous_translation.lerp(interpolated.current_translation, alpha);
+        transform.rotation = interpolated.previous_rotation.slerp(interpolated.current_rotation, alpha);
+    }
+}
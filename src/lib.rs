@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+use directories::ProjectDirs;
+use std::path::PathBuf;
+
+pub mod choice;
+pub mod controls;
+pub mod game;
+pub mod ghost;
+pub mod headless;
+pub mod highscores;
+pub mod interpolation;
+pub mod menu;
+pub mod replay;
+pub mod sim;
+
+pub use sim::GameOverCause;
+
+pub const BACKGROUND_COLOR: Color = Color::rgb(0.157, 0.8, 0.875);
+const TEXT_COLOR: Color = Color::rgb(0.188, 0.173, 0.18);
+
+const SCALE: Vec3 = Vec3 { x: sim::PIXEL_SCALE, y: sim::PIXEL_SCALE, z: 1. };
+
+pub const SCREEN_SIZE: Vec2 = Vec2 { x: sim::SCREEN_WIDTH, y: sim::SCREEN_HEIGHT };
+
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
+pub enum GameState {
+    #[default]
+    Menu,
+    Game,
+}
+
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
+pub enum MenuState {
+    #[default]
+    MainMenu,
+    Controls,
+    GameOver(GameOverCause),
+}
+
+// Where replays and other saves are kept, falls back to the working directory
+fn data_dir() -> PathBuf {
+    ProjectDirs::from("", "", "flappy-borb")
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
+    for entity in &to_despawn {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -3,40 +3,15 @@ use bevy::{
     window::{PresentMode, WindowTheme},
     core::FrameCount,
 };
-
-mod game;
-mod menu;
-
-const BACKGROUND_COLOR: Color = Color::rgb(0.157, 0.8, 0.875);
-const TEXT_COLOR: Color = Color::rgb(0.188, 0.173, 0.18);
-
-const SCALE: Vec3 = Vec3 { x: 3., y: 3., z: 1. };
-
-const SCREEN_SIZE: Vec2 = Vec2 { x: 500., y: 300. };
-const HALF_SCREEN_SIZE: Vec2 = Vec2 { x: SCREEN_SIZE.x / 2., y: SCREEN_SIZE.y / 2. };
-
-#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
-enum GameState {
-    #[default]
-    Menu,
-    Game,
-}
-
-#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
-enum GameOverCause {
-    TooHigh,
-    TooLow,
-    HitPipe,
-}
-
-#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
-enum MenuState {
-    #[default]
-    MainMenu,
-    GameOver(GameOverCause),
-}
+use flappy_borb::{choice, controls, game, ghost, headless, highscores, interpolation, menu, replay, GameState, MenuState, BACKGROUND_COLOR, SCREEN_SIZE};
 
 fn main() {
+    if std::env::args().any(|arg| arg == "--headless") {
+        headless::run();
+        return;
+    }
+
+    let seed = seed_from_args();
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -55,14 +30,39 @@ fn main() {
             ..Default::default()
         }).set(ImagePlugin::default_nearest()))
         .insert_resource(ClearColor(BACKGROUND_COLOR))
+        .insert_resource(game::NextSeed(seed))
+        .insert_resource(match seed {
+            Some(_) => game::GameMode::ChosenSeed,
+            None => game::GameMode::Normal,
+        })
         .add_systems(Startup, setup)
         .add_systems(Update, make_visible)
         .add_state::<GameState>()
         .add_state::<MenuState>()
-        .add_plugins((game::GamePlugin, menu::MenuPlugin))
+        .add_plugins((
+            interpolation::InterpolationPlugin,
+            controls::ControlsPlugin,
+            choice::ChoicePlugin,
+            game::GamePlugin,
+            ghost::GhostPlugin,
+            highscores::HighScorePlugin,
+            menu::MenuPlugin,
+            replay::ReplayPlugin,
+        ))
         .run();
 }
 
+// Reads `--seed <number>` so a run can be replayed from the command line
+fn seed_from_args() -> Option<u64> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            return args.next().and_then(|seed| seed.parse().ok());
+        }
+    }
+    None
+}
+
 fn setup(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
@@ -76,9 +76,3 @@ fn make_visible(mut window: Query<&mut Window>, frames: Res<FrameCount>) {
         window.single_mut().visible = true;
     }
 }
-
-fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
-    for entity in &to_despawn {
-        commands.entity(entity).despawn_recursive();
-    }
-}
\ No newline at end of file
@@ -1,15 +1,33 @@
 use bevy::{app::AppExit, prelude::*};
+use std::cmp::Ordering;
 use crate::{despawn_screen, GameState, TEXT_COLOR, MenuState, GameOverCause};
-use crate::game::Scoreboard;
+use crate::controls::{controls_path, Action, ActionInput, ActionMap, MAX_BINDINGS};
+use crate::game::{GameMode, GameRng, NextSeed};
+use crate::ghost::GhostRace;
+use crate::highscores::{HighScores, LastRun};
+use crate::replay::{best_replay_path, last_replay_path, Replay, ReplayPlayback};
+use crate::sim::Scoreboard;
 
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_systems(OnEnter(MenuState::MainMenu), main_menu_setup)
+            .add_systems(OnEnter(MenuState::MainMenu), main_menu_setup.run_if(in_state(GameState::Menu)))
             .add_systems(OnExit(MenuState::MainMenu), despawn_screen::<OnMainMenuScreen>)
 
+            .init_resource::<Rebinding>()
+            .init_resource::<MenuFocus>()
+            .add_systems(OnEnter(MenuState::Controls), controls_menu_setup)
+            .add_systems(OnExit(MenuState::Controls), despawn_screen::<OnControlsMenuScreen>)
+            .add_systems(
+                Update,
+                (start_rebinding, rebind_key, update_binding_labels)
+                    .chain()
+                    .after(press_focused_button)
+                    .run_if(in_state(MenuState::Controls)),
+            )
+
             .add_systems(OnEnter(MenuState::GameOver(GameOverCause::HitPipe)), game_over_menu_setup)
             .add_systems(OnEnter(MenuState::GameOver(GameOverCause::TooHigh)), game_over_menu_setup)
             .add_systems(OnEnter(MenuState::GameOver(GameOverCause::TooLow)), game_over_menu_setup)
@@ -20,7 +38,16 @@ impl Plugin for MenuPlugin {
             .add_systems(OnExit(GameState::Menu), despawn_screen::<OnMenuScreen>)
             .add_systems(
                 Update,
-                (menu_action, button_system).run_if(in_state(GameState::Menu)),
+                (
+                    go_back,
+                    navigate_menu,
+                    restart_from_game_over.run_if(in_game_over),
+                    press_focused_button,
+                    menu_action,
+                    button_system,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Menu)),
             );
     }
 }
@@ -34,11 +61,43 @@ struct OnMainMenuScreen;
 #[derive(Component)]
 struct OnGameOverMenuScreen;
 
+#[derive(Component)]
+struct OnControlsMenuScreen;
+
+// One of an action's keys on the controls screen, pressing it waits for a new key
+#[derive(Component)]
+struct BindingButton {
+    action: Action,
+    slot: usize,
+}
+
+// The button moved to with the arrow keys, tab or a gamepad, confirm presses it
+#[derive(Resource, Default)]
+struct MenuFocus(Option<Entity>);
+
+// The action and slot waiting for a key on the controls screen
+#[derive(Resource, Default)]
+struct Rebinding(Option<(Action, usize)>);
+
+// Keeps a flap held from the run that just ended from skipping the game over screen
+#[derive(Resource)]
+struct RestartDelay(Timer);
+
 const NORMAL_BUTTON: Color = Color::rgb(0.157, 0.8, 0.875);
 const HOVERED_BUTTON: Color = Color::rgb(0.224, 0.278, 0.471);
 // const HOVERED_PRESSED_BUTTON: Color = Color::rgb(0.224, 0.278, 0.471);
 const PRESSED_BUTTON: Color = Color::rgb(0.157, 0.8, 0.875);
 const MENU_BACKGROUND: Color = Color::rgb(0.224, 0.471, 0.659);
+const FOCUSED_BORDER: Color = TEXT_COLOR;
+const BUTTON_BORDER: f32 = 2.0;
+const RESTART_DELAY: f32 = 0.5;
+
+const HIGH_SCORE_TEXT_SIZE: f32 = 20.0;
+
+const CONTROLS_TEXT_SIZE: f32 = 16.0;
+const CONTROLS_ROW_HEIGHT: f32 = 24.0;
+const CONTROLS_NAME_WIDTH: f32 = 110.0;
+const BINDING_BUTTON_WIDTH: f32 = 110.0;
 
 // All actions that can be triggered from a button click
 #[derive(Component)]
@@ -46,30 +105,42 @@ enum MenuButtonAction {
     Play,
     Quit,
     ToMainMenu,
+    PlaySameSeed,
+    WatchReplay,
+    RaceGhost,
+    Controls,
 }
 
-// This system handles changing all buttons color based on mouse interaction
+// This system handles changing all buttons color based on mouse interaction, the focused button
+// looks hovered and gets a border
 fn button_system(
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<Button>),
+        (Entity, Ref<Interaction>, &mut BackgroundColor, &mut BorderColor),
+        With<Button>,
     >,
+    focus: Res<MenuFocus>,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    for (entity, interaction, mut color, mut border_color) in &mut interaction_query {
+        if !interaction.is_changed() && !focus.is_changed() {
+            continue;
+        }
+        let focused = focus.0 == Some(entity);
         *color = match *interaction {
             Interaction::Pressed => PRESSED_BUTTON.into(),
             Interaction::Hovered => HOVERED_BUTTON.into(),
+            Interaction::None if focused => HOVERED_BUTTON.into(),
             Interaction::None => NORMAL_BUTTON.into(),
-        }
+        };
+        border_color.0 = if focused { FOCUSED_BORDER } else { Color::NONE };
     }
 }
 
-fn main_menu_setup(mut commands: Commands) {
+fn main_menu_setup(mut commands: Commands, high_scores: Res<HighScores>) {
     // Common style for all buttons on the screen
     let button_style = Style {
-        width: Val::Px(140.0),
+        width: Val::Px(180.0),
         height: Val::Px(35.0),
-        margin: UiRect::all(Val::Px(20.0)),
+        margin: UiRect::all(Val::Px(6.0)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
@@ -79,6 +150,11 @@ fn main_menu_setup(mut commands: Commands) {
         color: TEXT_COLOR,
         ..default()
     };
+    let high_score_text_style = TextStyle {
+        font_size: HIGH_SCORE_TEXT_SIZE,
+        color: TEXT_COLOR,
+        ..default()
+    };
 
     commands
         .spawn((
@@ -118,40 +194,72 @@ fn main_menu_setup(mut commands: Commands) {
                             },
                         )
                         .with_style(Style {
-                            margin: UiRect::all(Val::Px(30.0)),
+                            margin: UiRect::all(Val::Px(15.0)),
                             ..default()
                         }),
                     );
 
-                    // Display two buttons for each action available from the main menu:
-                    // - new game
-                    // - quit
                     parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: NORMAL_BUTTON.into(),
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Row,
                                 ..default()
                             },
-                            MenuButtonAction::Play,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "New Game",
-                                button_text_style.clone(),
-                            ));
-                        });
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style,
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                            MenuButtonAction::Quit,
-                        ))
+                            ..default()
+                        })
                         .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("Quit", button_text_style));
+                            // Display buttons for each action available from the main menu:
+                            // - new game
+                            // - watch the last replay or race the best one, if they exist
+                            // - controls
+                            // - quit
+                            parent
+                                .spawn(NodeBundle {
+                                    style: Style {
+                                        flex_direction: FlexDirection::Column,
+                                        ..default()
+                                    },
+                                    ..default()
+                                })
+                                .with_children(|parent| {
+                                    spawn_button(parent, MenuButtonAction::Play, "New Game", &button_style, &button_text_style);
+                                    if last_replay_path().exists() {
+                                        spawn_button(parent, MenuButtonAction::WatchReplay, "Watch Replay", &button_style, &button_text_style);
+                                    }
+                                    if best_replay_path().exists() {
+                                        spawn_button(parent, MenuButtonAction::RaceGhost, "Race Ghost", &button_style, &button_text_style);
+                                    }
+                                    spawn_button(parent, MenuButtonAction::Controls, "Controls", &button_style, &button_text_style);
+                                    spawn_button(parent, MenuButtonAction::Quit, "Quit", &button_style, &button_text_style);
+                                });
+
+                            // Display the high score table next to the buttons
+                            parent
+                                .spawn(NodeBundle {
+                                    style: Style {
+                                        flex_direction: FlexDirection::Column,
+                                        padding: UiRect::all(Val::Px(10.0)),
+                                        ..default()
+                                    },
+                                    ..default()
+                                })
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section("High Scores", button_text_style.clone()));
+                                    if high_scores.entries.is_empty() {
+                                        parent.spawn(TextBundle::from_section("no runs yet", high_score_text_style.clone()));
+                                    }
+                                    for (rank, entry) in high_scores.entries.iter().enumerate() {
+                                        let mode = match entry.mode {
+                                            GameMode::Normal => "",
+                                            GameMode::ChosenSeed => " seed",
+                                            GameMode::GhostRace => " ghost",
+                                        };
+                                        parent.spawn(TextBundle::from_section(
+                                            format!("{}. {} {}{}", rank + 1, entry.score, entry.date, mode),
+                                            high_score_text_style.clone(),
+                                        ));
+                                    }
+                                });
                         });
                 });
         });
@@ -161,11 +269,14 @@ fn game_over_menu_setup(
     mut commands: Commands,
     menu_state: Res<State<MenuState>>,
     score: Res<Scoreboard>,
+    rng: Res<GameRng>,
+    last_run: Res<LastRun>,
 ) {
+    commands.insert_resource(RestartDelay(Timer::from_seconds(RESTART_DELAY, TimerMode::Once)));
     let button_style = Style {
         width: Val::Px(140.0),
         height: Val::Px(35.0),
-        margin: UiRect::all(Val::Px(20.0)),
+        margin: UiRect::all(Val::Px(10.0)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
@@ -213,7 +324,7 @@ fn game_over_menu_setup(
                             },
                         )
                         .with_style(Style {
-                            margin: UiRect::all(Val::Px(30.0)),
+                            margin: UiRect::all(Val::Px(20.0)),
                             ..default()
                         }),
                     );
@@ -223,6 +334,10 @@ fn game_over_menu_setup(
                         text_style.clone(),
                     ));
 
+                    if last_run.new_best {
+                        parent.spawn(TextBundle::from_section("New best!", text_style.clone()));
+                    }
+
                     if let MenuState::GameOver(cause) = menu_state.get() {
                         parent.spawn(TextBundle::from_section(
                             match cause {
@@ -234,23 +349,356 @@ fn game_over_menu_setup(
                         ));
                     }
 
+                    parent.spawn(TextBundle::from_section(
+                        format!("seed {}", rng.seed),
+                        text_style.clone(),
+                    ));
+
                     parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style,
-                                background_color: NORMAL_BUTTON.into(),
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Row,
                                 ..default()
                             },
-                            MenuButtonAction::ToMainMenu,
-                        ))
+                            ..default()
+                        })
                         .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("Main Menu", text_style));
+                            spawn_button(parent, MenuButtonAction::PlaySameSeed, "Same Seed", &button_style, &text_style);
+                            spawn_button(parent, MenuButtonAction::WatchReplay, "Replay", &button_style, &text_style);
+                            spawn_button(parent, MenuButtonAction::ToMainMenu, "Main Menu", &button_style, &text_style);
                         });
                 });
         });
 }
 
+fn controls_menu_setup(
+    mut commands: Commands,
+    action_map: Res<ActionMap>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    rebinding.0 = None;
+    let text_style = TextStyle {
+        font_size: CONTROLS_TEXT_SIZE,
+        color: TEXT_COLOR,
+        ..default()
+    };
+    let binding_button_style = Style {
+        width: Val::Px(BINDING_BUTTON_WIDTH),
+        height: Val::Px(CONTROLS_ROW_HEIGHT),
+        margin: UiRect::all(Val::Px(1.0)),
+        border: UiRect::all(Val::Px(BUTTON_BORDER)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnControlsMenuScreen,
+            OnMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    },
+                    background_color: MENU_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Controls",
+                        TextStyle {
+                            font_size: 30.0,
+                            color: TEXT_COLOR,
+                            ..default()
+                        },
+                    ));
+
+                    // One row per action with a button for each of its keys
+                    for action in Action::ALL {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Row,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn(
+                                    TextBundle::from_section(action.name(), text_style.clone())
+                                        .with_style(Style {
+                                            width: Val::Px(CONTROLS_NAME_WIDTH),
+                                            ..default()
+                                        }),
+                                );
+                                for slot in 0..MAX_BINDINGS {
+                                    parent
+                                        .spawn((
+                                            ButtonBundle {
+                                                style: binding_button_style.clone(),
+                                                background_color: NORMAL_BUTTON.into(),
+                                                border_color: Color::NONE.into(),
+                                                ..default()
+                                            },
+                                            BindingButton { action, slot },
+                                        ))
+                                        .with_children(|parent| {
+                                            parent.spawn(TextBundle::from_section(
+                                                binding_label(&action_map, action, slot),
+                                                text_style.clone(),
+                                            ));
+                                        });
+                                }
+                            });
+                    }
+
+                    spawn_button(
+                        parent,
+                        MenuButtonAction::ToMainMenu,
+                        "Back",
+                        &Style {
+                            width: Val::Px(BINDING_BUTTON_WIDTH),
+                            height: Val::Px(CONTROLS_ROW_HEIGHT),
+                            margin: UiRect::top(Val::Px(5.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        &text_style,
+                    );
+                });
+        });
+}
+
+fn binding_label(action_map: &ActionMap, action: Action, slot: usize) -> String {
+    match action_map.keys(action).get(slot) {
+        Some(key) => format!("{key:?}"),
+        None => "-".to_string(),
+    }
+}
+
+fn start_rebinding(
+    interaction_query: Query<(&Interaction, &BindingButton), Changed<Interaction>>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            rebinding.0 = Some((button.action, button.slot));
+        }
+    }
+}
+
+// Leaves the controls and game over screens. Runs before `rebind_key` so binding the back key
+// does not also leave the screen
+fn go_back(
+    action_input: ActionInput,
+    rebinding: Res<Rebinding>,
+    menu_state: Res<State<MenuState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+) {
+    if *menu_state.get() != MenuState::MainMenu && rebinding.0.is_none() && action_input.just_pressed(Action::Back) {
+        next_menu_state.set(MenuState::MainMenu);
+    }
+}
+
+fn in_game_over(menu_state: Res<State<MenuState>>) -> bool {
+    matches!(menu_state.get(), MenuState::GameOver(_))
+}
+
+// Flapping on the game over screen starts the next run, unless it is pressing a focused button
+#[allow(clippy::too_many_arguments)]
+fn restart_from_game_over(
+    action_input: ActionInput,
+    button_query: Query<(), With<Button>>,
+    focus: Res<MenuFocus>,
+    time: Res<Time>,
+    mut restart_delay: ResMut<RestartDelay>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut mode: ResMut<GameMode>,
+) {
+    restart_delay.0.tick(time.delta());
+    let focused = focus.0.is_some_and(|entity| button_query.contains(entity));
+    if !restart_delay.0.finished() || focused || !action_input.just_pressed(Action::Flap) {
+        return;
+    }
+    *mode = GameMode::Normal;
+    menu_state.set(MenuState::MainMenu);
+    game_state.set(GameState::Game);
+}
+
+// Waits for the frame after the binding button was pressed, so the key that pressed it is not
+// bound right away. Any gamepad button gives up on waiting.
+fn rebind_key(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut action_map: ResMut<ActionMap>,
+) {
+    if rebinding.is_changed() {
+        return;
+    }
+    let Some((action, slot)) = rebinding.0 else {
+        return;
+    };
+    if gamepad_input.get_just_pressed().next().is_some() {
+        rebinding.0 = None;
+        return;
+    }
+    let Some(key) = keyboard_input.get_just_pressed().next() else {
+        return;
+    };
+    action_map.rebind(action, slot, *key);
+    rebinding.0 = None;
+    if let Err(error) = action_map.save(&controls_path()) {
+        warn!("could not save controls: {error}");
+    }
+}
+
+fn update_binding_labels(
+    button_query: Query<(&BindingButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+    action_map: Res<ActionMap>,
+    rebinding: Res<Rebinding>,
+) {
+    if !action_map.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for (button, children) in &button_query {
+        let label = if rebinding.0 == Some((button.action, button.slot)) {
+            "press a key".to_string()
+        } else {
+            binding_label(&action_map, button.action, button.slot)
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
+
+// Top to bottom, then left to right
+fn reading_order(a: &GlobalTransform, b: &GlobalTransform) -> Ordering {
+    let (a, b) = (a.translation(), b.translation());
+    a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+}
+
+// Moves the focus to the closest button in the pushed direction, the first push focuses the top left one.
+// Tab goes through the buttons in reading order instead, backwards with shift.
+fn navigate_menu(
+    mut action_input: ActionInput,
+    keyboard_input: Res<Input<KeyCode>>,
+    button_query: Query<(Entity, &GlobalTransform), With<Button>>,
+    rebinding: Res<Rebinding>,
+    mut focus: ResMut<MenuFocus>,
+) {
+    let direction = action_input.navigation();
+    if rebinding.0.is_some() {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        let mut buttons: Vec<_> = button_query.iter().collect();
+        buttons.sort_by(|(_, a), (_, b)| reading_order(a, b));
+        let backwards = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let count = buttons.len();
+        let next = match focus.0.and_then(|entity| buttons.iter().position(|(button, _)| *button == entity)) {
+            Some(index) if backwards => (index + count - 1) % count,
+            Some(index) => (index + 1) % count,
+            None if backwards => count.saturating_sub(1),
+            None => 0,
+        };
+        focus.0 = buttons.get(next).map(|(entity, _)| *entity);
+        return;
+    }
+
+    let Some(direction) = direction else {
+        return;
+    };
+    let Some(from) = focus.0.and_then(|entity| button_query.get(entity).ok()) else {
+        focus.0 = button_query
+            .iter()
+            .min_by(|(_, a), (_, b)| reading_order(a, b))
+            .map(|(entity, _)| entity);
+        return;
+    };
+    let from = from.1.translation().truncate();
+    let step = direction.vector();
+    let closest = button_query
+        .iter()
+        .filter_map(|(entity, transform)| {
+            let offset = transform.translation().truncate() - from;
+            let along = offset.dot(step);
+            // Buttons off to the side count as further away than ones straight ahead
+            (along > 0.).then(|| (entity, along + offset.perp_dot(step).abs() * 2.))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+    if let Some((entity, _)) = closest {
+        focus.0 = Some(entity);
+    }
+}
+
+// Presses the focused button the same way a click would, so `menu_action` handles both
+fn press_focused_button(
+    action_input: ActionInput,
+    focus: Res<MenuFocus>,
+    rebinding: Res<Rebinding>,
+    mut interaction_query: Query<&mut Interaction, With<Button>>,
+) {
+    if rebinding.0.is_some() || !action_input.just_pressed(Action::Confirm) {
+        return;
+    }
+    if let Some(mut interaction) = focus.0.and_then(|entity| interaction_query.get_mut(entity).ok()) {
+        *interaction = Interaction::Pressed;
+    }
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    action: MenuButtonAction,
+    label: &str,
+    style: &Style,
+    text_style: &TextStyle,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    border: UiRect::all(Val::Px(BUTTON_BORDER)),
+                    ..style.clone()
+                },
+                background_color: NORMAL_BUTTON.into(),
+                border_color: Color::NONE.into(),
+                ..default()
+            },
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn menu_action(
+    mut commands: Commands,
     interaction_query: Query<
         (&Interaction, &MenuButtonAction),
         (Changed<Interaction>, With<Button>),
@@ -258,6 +706,9 @@ fn menu_action(
     mut app_exit_events: EventWriter<AppExit>,
     mut game_state: ResMut<NextState<GameState>>,
     mut menu_state: ResMut<NextState<MenuState>>,
+    mut next_seed: ResMut<NextSeed>,
+    mut mode: ResMut<GameMode>,
+    rng: Option<Res<GameRng>>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -266,11 +717,48 @@ fn menu_action(
                     app_exit_events.send(AppExit);
                 }
                 MenuButtonAction::Play => {
+                    // A seed from the command line is still waiting for the first run
+                    *mode = if next_seed.0.is_some() { GameMode::ChosenSeed } else { GameMode::Normal };
                     game_state.set(GameState::Game);
                 }
                 MenuButtonAction::ToMainMenu => {
                     menu_state.set(MenuState::MainMenu);
                 }
+                MenuButtonAction::Controls => {
+                    menu_state.set(MenuState::Controls);
+                }
+                MenuButtonAction::PlaySameSeed => {
+                    if let Some(rng) = &rng {
+                        next_seed.0 = Some(rng.seed);
+                    }
+                    *mode = GameMode::ChosenSeed;
+                    // Leave the game over state so the next game over enters it again
+                    menu_state.set(MenuState::MainMenu);
+                    game_state.set(GameState::Game);
+                }
+                MenuButtonAction::WatchReplay => {
+                    match Replay::load(&last_replay_path()) {
+                        Ok(replay) => {
+                            next_seed.0 = Some(replay.seed);
+                            commands.insert_resource(ReplayPlayback::new(replay));
+                            menu_state.set(MenuState::MainMenu);
+                            game_state.set(GameState::Game);
+                        }
+                        Err(error) => warn!("could not load replay: {error}"),
+                    }
+                }
+                MenuButtonAction::RaceGhost => {
+                    match Replay::load(&best_replay_path()) {
+                        Ok(replay) => {
+                            next_seed.0 = Some(replay.seed);
+                            commands.insert_resource(GhostRace(replay));
+                            *mode = GameMode::GhostRace;
+                            menu_state.set(MenuState::MainMenu);
+                            game_state.set(GameState::Game);
+                        }
+                        Err(error) => warn!("could not load ghost replay: {error}"),
+                    }
+                }
             }
         }
     }
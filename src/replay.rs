@@ -0,0 +1,240 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::{Path, PathBuf}};
+use crate::{data_dir, GameState};
+use crate::game::{Borb, BorbInput, ChooseUpgrade, GameRng, InputSet, SimulationTick, UpgradeChoice, UpgradeChoiceSet};
+use crate::interpolation::SimulationSet;
+use crate::sim::{Gravity, Scoreboard};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ReplayRecorder>()
+            .add_systems(OnEnter(GameState::Game), start_recording)
+            .add_systems(FixedUpdate, (
+                record_input.run_if(not(resource_exists::<ReplayPlayback>())),
+                play_back_input.run_if(resource_exists::<ReplayPlayback>()),
+            ).in_set(InputSet).run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, record_flight
+                .in_set(SimulationSet::Capture)
+                .run_if(in_state(GameState::Game)))
+            .add_systems(Update, (
+                record_choice.after(UpgradeChoiceSet).run_if(not(resource_exists::<ReplayPlayback>())),
+                play_back_choice.in_set(UpgradeChoiceSet).run_if(resource_exists::<ReplayPlayback>()),
+            ).run_if(in_state(GameState::Game).and_then(resource_exists::<UpgradeChoice>())))
+            .add_systems(OnExit(GameState::Game), (
+                save_replay.run_if(not(resource_exists::<ReplayPlayback>())),
+                stop_playback,
+            ).chain());
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ReplayAction {
+    Jump,
+    FastFall,
+    Hover,
+    BulletTime,
+    // Glide is held, so only pressing and letting go are recorded
+    Glide(bool),
+    // The upgrade picked on the milestone reached during this tick
+    Choose(u8),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct ReplayInput {
+    pub tick: u32,
+    pub action: ReplayAction,
+}
+
+// Where the borb was at the end of a tick, wind moves it sideways and gravity inversion flips it
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+pub struct FlightPoint {
+    pub x: f32,
+    pub y: f32,
+    pub inverted: bool,
+}
+
+// Everything needed to play a run again, the seed rebuilds the world and the inputs drive the borb.
+// The recorded flight and scoring ticks let a ghost follow the run without simulating it.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub score: u32,
+    pub inputs: Vec<ReplayInput>,
+    #[serde(default)]
+    pub flight: Vec<FlightPoint>,
+    #[serde(default)]
+    pub score_ticks: Vec<u32>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = ron::to_string(self).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::write(path, text)
+    }
+}
+
+pub fn last_replay_path() -> PathBuf {
+    data_dir().join("replays").join("last.ron")
+}
+
+pub fn best_replay_path() -> PathBuf {
+    data_dir().join("replays").join("best.ron")
+}
+
+// resources
+#[derive(Resource, Default)]
+struct ReplayRecorder {
+    inputs: Vec<ReplayInput>,
+    gliding: bool,
+    flight: Vec<FlightPoint>,
+    score_ticks: Vec<u32>,
+}
+
+// Present while a replay is being watched, the keyboard is ignored until the run ends
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    next_input: usize,
+    gliding: bool,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, next_input: 0, gliding: false }
+    }
+}
+
+// systems
+fn start_recording(mut recorder: ResMut<ReplayRecorder>) {
+    *recorder = ReplayRecorder::default();
+}
+
+fn record_input(
+    input: Res<BorbInput>,
+    tick: Res<SimulationTick>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    if input.jump {
+        recorder.inputs.push(ReplayInput { tick: tick.0, action: ReplayAction::Jump });
+    }
+    if input.fast_fall {
+        recorder.inputs.push(ReplayInput { tick: tick.0, action: ReplayAction::FastFall });
+    }
+    if input.hover {
+        recorder.inputs.push(ReplayInput { tick: tick.0, action: ReplayAction::Hover });
+    }
+    if input.bullet_time {
+        recorder.inputs.push(ReplayInput { tick: tick.0, action: ReplayAction::BulletTime });
+    }
+    if input.glide != recorder.gliding {
+        recorder.gliding = input.glide;
+        recorder.inputs.push(ReplayInput { tick: tick.0, action: ReplayAction::Glide(input.glide) });
+    }
+}
+
+fn record_choice(
+    mut choose_events: EventReader<ChooseUpgrade>,
+    choice: Res<UpgradeChoice>,
+    tick: Res<SimulationTick>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    if let Some(event) = choose_events.read().find(|event| event.0 < choice.options.len()) {
+        recorder.inputs.push(ReplayInput { tick: tick.0, action: ReplayAction::Choose(event.0 as u8) });
+    }
+}
+
+fn record_flight(
+    borb_query: Query<(&Transform, &Gravity), With<Borb>>,
+    tick: Res<SimulationTick>,
+    score: Res<Scoreboard>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    let (transform, gravity) = borb_query.single();
+    recorder.flight.push(FlightPoint {
+        x: transform.translation.x,
+        y: transform.translation.y,
+        inverted: gravity.inverted,
+    });
+    while (recorder.score_ticks.len() as u32) < score.score {
+        recorder.score_ticks.push(tick.0);
+    }
+}
+
+fn play_back_input(
+    mut input: ResMut<BorbInput>,
+    tick: Res<SimulationTick>,
+    mut playback: ResMut<ReplayPlayback>,
+) {
+    *input = BorbInput::default();
+    while let Some(recorded) = playback.replay.inputs.get(playback.next_input) {
+        if recorded.tick > tick.0 {
+            break;
+        }
+        match recorded.action {
+            ReplayAction::Jump => input.jump = true,
+            ReplayAction::FastFall => input.fast_fall = true,
+            ReplayAction::Hover => input.hover = true,
+            ReplayAction::BulletTime => input.bullet_time = true,
+            ReplayAction::Glide(held) => playback.gliding = held,
+            // Left for `play_back_choice` once this tick reaches the milestone
+            ReplayAction::Choose(_) if recorded.tick == tick.0 => break,
+            ReplayAction::Choose(_) => {}
+        }
+        playback.next_input += 1;
+    }
+    input.glide = playback.gliding;
+}
+
+// Replays without a recorded choice, like headless runs, take the first option
+fn play_back_choice(
+    mut choose_events: EventWriter<ChooseUpgrade>,
+    mut playback: ResMut<ReplayPlayback>,
+) {
+    let index = match playback.replay.inputs.get(playback.next_input).map(|recorded| recorded.action) {
+        Some(ReplayAction::Choose(index)) => {
+            playback.next_input += 1;
+            index as usize
+        }
+        _ => 0,
+    };
+    choose_events.send(ChooseUpgrade(index));
+}
+
+fn save_replay(
+    recorder: Res<ReplayRecorder>,
+    rng: Res<GameRng>,
+    score: Res<Scoreboard>,
+) {
+    let replay = Replay {
+        seed: rng.seed,
+        score: score.score,
+        inputs: recorder.inputs.clone(),
+        flight: recorder.flight.clone(),
+        score_ticks: recorder.score_ticks.clone(),
+    };
+    if let Err(error) = replay.save(&last_replay_path()) {
+        warn!("could not save replay: {error}");
+    }
+    let is_best = Replay::load(&best_replay_path()).map_or(true, |best| replay.score > best.score);
+    if is_best {
+        if let Err(error) = replay.save(&best_replay_path()) {
+            warn!("could not save best replay: {error}");
+        }
+    }
+}
+
+fn stop_playback(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayback>();
+}
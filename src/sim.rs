@@ -0,0 +1,1050 @@
+// Gameplay rules without any Bevy types, the systems in `game` apply them to entities.
+// Keeping them here lets tests drive the physics, scrolling, collisions and scoring directly.
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::{Deserialize, Serialize};
+
+// consts
+pub const PIXEL_SCALE: f32 = 3.;
+
+pub const SCREEN_WIDTH: f32 = 500.;
+pub const SCREEN_HEIGHT: f32 = 300.;
+pub const HALF_SCREEN_HEIGHT: f32 = SCREEN_HEIGHT / 2.;
+
+pub const PIPE_SPEED: f32 = 50.0;
+pub const HALF_PIPE_SPACE: f32 = 50.0;
+// Leaves 20 pixels between the 64 pixel tall pipes, twice the height of the borb's collider
+pub const MIN_HALF_PIPE_SPACE: f32 = 42.0;
+pub const NARROW_GAP_PER_LEVEL: f32 = 3.;
+pub const MAX_PIPE_HOLE_Y: f32 = 80.;
+pub const PIPE_WIDTH: f32 = PIXEL_SCALE * 16.;
+pub const SCREEN_WIDTH_WITH_PIPE: f32 = SCREEN_WIDTH + PIPE_WIDTH;
+pub const HALF_SCREEN_WIDTH_WITH_HALF_PIPE: f32 = SCREEN_WIDTH_WITH_PIPE / 2.;
+const MIDDLE_PIPE_CHANCE: f32 = 0.2;
+const MIDDLE_PIPE_CHANCE_PER_LEVEL: f32 = 0.1;
+pub const PIPE_BOB_AMPLITUDE: f32 = 20.;
+pub const PIPE_BOB_AMPLITUDE_PER_LEVEL: f32 = 10.;
+// In pipe sprite pixels since the pipes are scaled up with their pair
+pub const PIPE_GAP_AMPLITUDE: f32 = 4.;
+pub const PIPE_GAP_AMPLITUDE_PER_LEVEL: f32 = 2.;
+pub const PIPE_OSCILLATION_FREQUENCY: f32 = 0.4;
+pub const PIPE_OSCILLATION_FREQUENCY_PER_LEVEL: f32 = 0.15;
+
+pub const GRAVITY: f32 = 140.0;
+pub const MAX_GRAVITY: f32 = -140.0;
+pub const JUMP_FORCE: f32 = 110.0;
+pub const FAST_FALL_SPEED: f32 = -160.;
+pub const FAST_FALL_SPEED_PER_LEVEL: f32 = -40.;
+pub const PIPE_SPEED_PER_LEVEL: f32 = 25.;
+
+pub const GLIDE_FALL_SPEED: f32 = -35.;
+pub const GLIDE_STAMINA: f32 = 1.5;
+pub const GLIDE_STAMINA_PER_LEVEL: f32 = 1.;
+pub const GLIDE_RECHARGE_SPEED: f32 = 0.5;
+pub const HOVER_TIME: f32 = 0.6;
+pub const HOVER_COOLDOWN: f32 = 4.;
+pub const HOVER_COOLDOWN_PER_LEVEL: f32 = -1.;
+pub const BULLET_TIME_SCALE: f32 = 0.5;
+pub const BULLET_TIME_DURATION: f32 = 3.;
+pub const BULLET_TIME_DURATION_PER_LEVEL: f32 = 1.5;
+pub const BULLET_TIME_COOLDOWN: f32 = 12.;
+pub const SHRINK_PER_LEVEL: f32 = 0.8;
+const SIZE_TWEEN_RATE: f32 = 6.;
+const SIZE_SNAP_DISTANCE: f32 = 0.001;
+
+pub const WIND_WARNING_TIME: f32 = 1.;
+pub const WIND_GUST_TIME: f32 = 1.5;
+pub const WIND_CALM_TIME: f32 = 3.;
+pub const WIND_STRENGTH: f32 = 50.;
+pub const WIND_STRENGTH_PER_LEVEL: f32 = 25.;
+pub const WIND_SPRING: f32 = 3.;
+pub const GRAVITY_FLIP_TIME: f32 = 10.;
+pub const GRAVITY_FLIP_TIME_PER_LEVEL: f32 = -3.;
+pub const GRAVITY_FLIP_WARNING_TIME: f32 = 3.;
+pub const DARKNESS_RADIUS: f32 = 90.;
+pub const DARKNESS_RADIUS_PER_LEVEL: f32 = -20.;
+pub const DARKNESS_FLASH_RADIUS: f32 = 60.;
+pub const DARKNESS_FLASH_TIME: f32 = 0.4;
+pub const CONTROL_SCRAMBLE_CALM_TIME: f32 = 8.;
+pub const CONTROL_SCRAMBLE_TIME: f32 = 4.;
+pub const CONTROL_SCRAMBLE_TIME_PER_LEVEL: f32 = 1.5;
+pub const CONTROL_DELAY: f32 = 0.2;
+pub const CONTROL_DELAY_PER_LEVEL: f32 = 0.1;
+
+pub const MAX_UPGRADES: u32 = 5;
+pub const MAX_CHAOS: u32 = 5;
+pub const PIPES_PER_UPGRADE: u32 = 5;
+pub const UPGRADE_CHOICES: usize = 3;
+
+// enums
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum GameOverCause {
+    TooHigh,
+    TooLow,
+    HitPipe,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum PipeType {
+    Normal,
+    Middle,
+}
+
+// How a pipe pair moves while moving pipes is active
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum PipeMotion {
+    Still,
+    // The whole pair goes up and down
+    Bob,
+    // The gap opens and closes around the pair's own gap
+    Breathe,
+}
+
+// What happened to a pipe pair during one step of `scroll_pipe`
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum PipeEvent {
+    Moved,
+    Wrapped,
+    Passed,
+}
+
+// borb physics
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Gravity {
+    pub gravity: f32,
+    // The dive speed while fast falling, it replaces the normal fall speed cap
+    pub fast_fall_speed: Option<f32>,
+    // Set by `Glide` and `Hover` every step
+    pub glide_speed: Option<f32>,
+    pub hovering: bool,
+    // Everything above is measured along the current pull, `step` turns it upside down while inverted
+    pub inverted: bool,
+}
+
+impl Gravity {
+    pub fn jump(&mut self) {
+        self.gravity = JUMP_FORCE;
+        self.fast_fall_speed = None;
+    }
+
+    pub fn fast_fall(&mut self, speed: f32) {
+        self.gravity = speed;
+        self.fast_fall_speed = Some(speed);
+    }
+
+    // Turns the pull around while keeping the borb moving the same way it was
+    pub fn flip(&mut self) {
+        self.inverted = !self.inverted;
+        self.gravity = -self.gravity;
+        self.fast_fall_speed = None;
+    }
+
+    // Accelerates along the pull up to the fall speed cap and returns how far to move up this step
+    pub fn step(&mut self, delta_seconds: f32) -> f32 {
+        if self.hovering {
+            self.gravity = 0.;
+            return 0.;
+        }
+        self.gravity -= GRAVITY * delta_seconds;
+        self.gravity = self.gravity.max(self.glide_speed.or(self.fast_fall_speed).unwrap_or(MAX_GRAVITY));
+        let distance = self.gravity * delta_seconds;
+        if self.inverted { -distance } else { distance }
+    }
+}
+
+// Holding glide while falling slows the fall down to `GLIDE_FALL_SPEED` until stamina runs out,
+// stamina comes back while glide is let go
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Glide {
+    pub stamina: f32,
+    pub max_stamina: f32,
+    pub gliding: bool,
+}
+
+impl Glide {
+    // `max_stamina` comes from the glide level, any stamina gained with a level is handed out right away
+    pub fn step(&mut self, gravity: &mut Gravity, held: bool, max_stamina: f32, delta_seconds: f32) {
+        if max_stamina > self.max_stamina {
+            self.stamina += max_stamina - self.max_stamina;
+        }
+        self.max_stamina = max_stamina;
+        self.gliding = held && self.stamina > 0. && gravity.gravity <= 0.;
+        if self.gliding {
+            self.stamina = (self.stamina - delta_seconds).max(0.);
+            gravity.fast_fall_speed = None;
+            gravity.glide_speed = Some(GLIDE_FALL_SPEED);
+        } else {
+            gravity.glide_speed = None;
+            if !held {
+                self.stamina = (self.stamina + GLIDE_RECHARGE_SPEED * delta_seconds).min(max_stamina);
+            }
+        }
+    }
+}
+
+// Hovering holds the borb still in the air for `HOVER_TIME`, a jump cuts it short
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Hover {
+    pub remaining: f32,
+    pub cooldown: f32,
+}
+
+impl Hover {
+    // Starts hovering unless it is still cooling down
+    pub fn trigger(&mut self, gravity: &mut Gravity, cooldown: f32) -> bool {
+        if self.cooldown > 0. {
+            return false;
+        }
+        self.remaining = HOVER_TIME;
+        self.cooldown = cooldown;
+        gravity.gravity = 0.;
+        true
+    }
+
+    pub fn step(&mut self, gravity: &mut Gravity, delta_seconds: f32) {
+        self.cooldown = (self.cooldown - delta_seconds).max(0.);
+        if gravity.gravity > 0. {
+            self.remaining = 0.;
+        }
+        self.remaining = (self.remaining - delta_seconds).max(0.);
+        gravity.hovering = self.remaining > 0.;
+        if gravity.hovering {
+            gravity.fast_fall_speed = None;
+        }
+    }
+}
+
+// Slows the world down for a while, counted in unscaled time so it lasts as long as it says
+#[derive(Clone, Copy, Default, Debug)]
+pub struct BulletTime {
+    pub remaining: f32,
+    pub cooldown: f32,
+}
+
+impl BulletTime {
+    // Starts bullet time unless it is still cooling down, the cooldown starts once it is over
+    pub fn trigger(&mut self, duration: f32) -> bool {
+        if self.cooldown > 0. {
+            return false;
+        }
+        self.remaining = duration;
+        self.cooldown = duration + BULLET_TIME_COOLDOWN;
+        true
+    }
+
+    // Returns how fast the world should run during this step
+    pub fn step(&mut self, delta_seconds: f32) -> f32 {
+        let active = self.remaining > 0.;
+        self.remaining = (self.remaining - delta_seconds).max(0.);
+        self.cooldown = (self.cooldown - delta_seconds).max(0.);
+        if active { BULLET_TIME_SCALE } else { 1. }
+    }
+}
+
+// Counts down to the next time gravity flips while gravity inversion is active
+#[derive(Clone, Copy, Default, Debug)]
+pub struct GravityFlip {
+    pub countdown: Option<f32>,
+}
+
+impl GravityFlip {
+    // `interval` comes from the gravity inversion level, without it gravity goes back to normal.
+    // Returns whether gravity flipped this step
+    pub fn step(&mut self, gravity: &mut Gravity, interval: Option<f32>, delta_seconds: f32) -> bool {
+        let Some(interval) = interval else {
+            self.countdown = None;
+            if gravity.inverted {
+                gravity.flip();
+                return true;
+            }
+            return false;
+        };
+        let countdown = self.countdown.unwrap_or(interval) - delta_seconds;
+        if countdown <= 0. {
+            gravity.flip();
+            self.countdown = Some(countdown + interval);
+            true
+        } else {
+            self.countdown = Some(countdown);
+            false
+        }
+    }
+
+    // Whole seconds left before the next flip once it is close enough to warn about
+    pub fn warning(&self) -> Option<u32> {
+        self.countdown
+            .filter(|countdown| *countdown <= GRAVITY_FLIP_WARNING_TIME)
+            .map(|countdown| countdown.ceil() as u32)
+    }
+}
+
+// How far around the borb can be seen while darkness is active, jumping lights it up for a moment
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Darkness {
+    pub flash: f32,
+    pub radius: f32,
+}
+
+impl Darkness {
+    pub fn flash(&mut self) {
+        self.flash = DARKNESS_FLASH_TIME;
+    }
+
+    // `radius` comes from the darkness level, the flash fades out linearly
+    pub fn step(&mut self, radius: f32, delta_seconds: f32) -> f32 {
+        self.flash = (self.flash - delta_seconds).max(0.);
+        self.radius = radius + DARKNESS_FLASH_RADIUS * self.flash / DARKNESS_FLASH_TIME;
+        self.radius
+    }
+}
+
+// scrambled controls
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Scramble {
+    // Jump and fast fall swap places
+    Reversed,
+    // Jumps only happen a moment after they were pressed
+    Delayed,
+}
+
+// How long the controls stay scrambled and how late delayed jumps come in, both in seconds
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Scrambling {
+    pub duration: f32,
+    pub delay: f32,
+}
+
+// Every so often scrambles the controls for a while, with calm spells in between
+#[derive(Clone, Default, Debug)]
+pub struct ControlScramble {
+    pub active: Option<Scramble>,
+    pub timer: f32,
+    delayed_jumps: Vec<f32>,
+}
+
+impl ControlScramble {
+    // Returns the scramble that started this step
+    pub fn step(&mut self, scrambling: Option<Scrambling>, rng: &mut impl Rng, delta_seconds: f32) -> Option<Scramble> {
+        let Some(scrambling) = scrambling else {
+            self.active = None;
+            self.timer = 0.;
+            return None;
+        };
+        self.timer += delta_seconds;
+        match self.active {
+            None if self.timer >= CONTROL_SCRAMBLE_CALM_TIME => {
+                let scramble = if rng.gen_bool(0.5) { Scramble::Reversed } else { Scramble::Delayed };
+                self.active = Some(scramble);
+                self.timer = 0.;
+                Some(scramble)
+            }
+            Some(_) if self.timer >= scrambling.duration => {
+                self.active = None;
+                self.timer = 0.;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    // Turns the jump and fast fall that were pressed into the ones the borb gets this step,
+    // jumps that were held back still come in after the scramble is over
+    pub fn route(&mut self, jump: bool, fast_fall: bool, scrambling: Option<Scrambling>, delta_seconds: f32) -> (bool, bool) {
+        let waiting = self.delayed_jumps.len();
+        for remaining in &mut self.delayed_jumps {
+            *remaining -= delta_seconds;
+        }
+        self.delayed_jumps.retain(|remaining| *remaining > 0.);
+        let delayed_jump = self.delayed_jumps.len() < waiting;
+        match (self.active, scrambling) {
+            (Some(Scramble::Reversed), _) => (fast_fall || delayed_jump, jump),
+            (Some(Scramble::Delayed), Some(scrambling)) => {
+                if jump {
+                    self.delayed_jumps.push(scrambling.delay);
+                }
+                (delayed_jump, fast_fall)
+            }
+            _ => (jump || delayed_jump, fast_fall),
+        }
+    }
+}
+
+// Eases a size multiplier towards its target, settling exactly on it once close enough
+pub fn tween_size(current: f32, target: f32, delta_seconds: f32) -> f32 {
+    let next = current + (target - current) * (1. - (-SIZE_TWEEN_RATE * delta_seconds).exp());
+    if (target - next).abs() < SIZE_SNAP_DISTANCE { target } else { next }
+}
+
+// wind
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum WindPhase {
+    Calm,
+    // The next gust is known and shown but does not blow yet
+    Warning,
+    Gust,
+}
+
+// Gusts come from a random direction after a random calm, each one announced `WIND_WARNING_TIME` ahead
+#[derive(Clone, Copy, Debug)]
+pub struct Wind {
+    pub phase: WindPhase,
+    pub timer: f32,
+    // Unit direction of the current or upcoming gust
+    pub direction: (f32, f32),
+    pub strength: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            phase: WindPhase::Calm,
+            timer: WIND_CALM_TIME,
+            direction: (0., 0.),
+            strength: 0.,
+        }
+    }
+}
+
+impl Wind {
+    // Each gust blows in a random direction after a calm of random length
+    pub fn step(&mut self, strength: Option<f32>, rng: &mut impl Rng, delta_seconds: f32) {
+        let Some(strength) = strength else {
+            return;
+        };
+        self.strength = strength;
+        self.timer -= delta_seconds;
+        if self.timer > 0. {
+            return;
+        }
+        match self.phase {
+            WindPhase::Calm => {
+                let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+                self.direction = (angle.cos(), angle.sin());
+                self.phase = WindPhase::Warning;
+                self.timer += WIND_WARNING_TIME;
+            }
+            WindPhase::Warning => {
+                self.phase = WindPhase::Gust;
+                self.timer += WIND_GUST_TIME;
+            }
+            WindPhase::Gust => {
+                self.phase = WindPhase::Calm;
+                self.timer += WIND_CALM_TIME * (1. + rng.gen::<f32>());
+            }
+        }
+    }
+
+    // The velocity the wind adds right now
+    pub fn velocity(&self) -> (f32, f32) {
+        match self.phase {
+            WindPhase::Gust => (self.direction.0 * self.strength, self.direction.1 * self.strength),
+            WindPhase::Calm | WindPhase::Warning => (0., 0.),
+        }
+    }
+}
+
+// Moves `x` with the wind while a spring pulls it back towards `home_x`
+pub fn drift(x: f32, home_x: f32, wind_x: f32, delta_seconds: f32) -> f32 {
+    x + (wind_x - WIND_SPRING * (x - home_x)) * delta_seconds
+}
+
+pub fn out_of_bounds(y: f32, half_height: f32) -> Option<GameOverCause> {
+    if y < -HALF_SCREEN_HEIGHT - half_height {
+        Some(GameOverCause::TooLow)
+    } else if y > HALF_SCREEN_HEIGHT + half_height {
+        Some(GameOverCause::TooHigh)
+    } else {
+        None
+    }
+}
+
+// pipes
+// `passed` is kept per pipe pair so it only scores once per trip across the screen
+pub fn scroll_pipe(x: &mut f32, passed: &mut bool, borb_x: f32, speed: f32, delta_seconds: f32) -> PipeEvent {
+    *x -= speed * delta_seconds;
+    if *x < -HALF_SCREEN_WIDTH_WITH_HALF_PIPE {
+        *x = HALF_SCREEN_WIDTH_WITH_HALF_PIPE;
+        *passed = false;
+        PipeEvent::Wrapped
+    } else if !*passed && *x < borb_x {
+        *passed = true;
+        PipeEvent::Passed
+    } else {
+        PipeEvent::Moved
+    }
+}
+
+pub fn random_pipe_hole_y(rng: &mut impl Rng) -> f32 {
+    rng.gen::<f32>() * MAX_PIPE_HOLE_Y * 2.0 - MAX_PIPE_HOLE_Y
+}
+
+// Amplitudes and frequency of moving pipes, `frequency` is in swings per second
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Oscillation {
+    pub bob_amplitude: f32,
+    pub gap_amplitude: f32,
+    pub frequency: f32,
+}
+
+// A pipe pair's hole and gap as it moves, picked again every time the pair wraps around.
+// `half_gap` is how far each pipe sits from the hole, like `HALF_PIPE_SPACE`
+#[derive(Clone, Copy, Debug)]
+pub struct OscillatingPipe {
+    pub motion: PipeMotion,
+    pub hole_y: f32,
+    pub half_gap: f32,
+    pub time: f32,
+}
+
+impl OscillatingPipe {
+    // Middle pipes have no gap so they can only bob
+    pub fn new(
+        pipe_type: PipeType,
+        hole_y: f32,
+        half_gap: f32,
+        oscillation: Option<Oscillation>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let motion = match (oscillation, pipe_type) {
+            (None, _) => PipeMotion::Still,
+            (Some(_), PipeType::Middle) => PipeMotion::Bob,
+            (Some(_), PipeType::Normal) if rng.gen_bool(0.5) => PipeMotion::Bob,
+            (Some(_), PipeType::Normal) => PipeMotion::Breathe,
+        };
+        // Keep a bobbing hole inside the range a still one could have been rolled in
+        let hole_y = match (motion, oscillation) {
+            (PipeMotion::Bob, Some(oscillation)) => {
+                let max_y = (MAX_PIPE_HOLE_Y - oscillation.bob_amplitude).max(0.);
+                hole_y.clamp(-max_y, max_y)
+            }
+            _ => hole_y,
+        };
+        Self { motion, hole_y, half_gap, time: 0. }
+    }
+
+    // Returns where the hole is and how far each pipe is from it this step,
+    // a closing gap never gets narrower than `MIN_HALF_PIPE_SPACE`
+    pub fn step(&mut self, oscillation: Option<Oscillation>, delta_seconds: f32) -> (f32, f32) {
+        let Some(oscillation) = oscillation else {
+            return (self.hole_y, self.half_gap);
+        };
+        self.time += delta_seconds;
+        let swing = (self.time * oscillation.frequency * std::f32::consts::TAU).sin();
+        match self.motion {
+            PipeMotion::Still => (self.hole_y, self.half_gap),
+            PipeMotion::Bob => (self.hole_y + swing * oscillation.bob_amplitude, self.half_gap),
+            PipeMotion::Breathe => {
+                let half_gap = self.half_gap + swing * oscillation.gap_amplitude;
+                (self.hole_y, half_gap.max(MIN_HALF_PIPE_SPACE))
+            }
+        }
+    }
+}
+
+// Somewhere between `min_half_gap` and `HALF_PIPE_SPACE`
+pub fn random_pipe_half_gap(min_half_gap: f32, rng: &mut impl Rng) -> f32 {
+    if min_half_gap >= HALF_PIPE_SPACE {
+        HALF_PIPE_SPACE
+    } else {
+        rng.gen_range(min_half_gap.max(MIN_HALF_PIPE_SPACE)..=HALF_PIPE_SPACE)
+    }
+}
+
+pub struct ObstacleGenerator {
+    last_generated: PipeType,
+}
+
+impl Default for ObstacleGenerator {
+    fn default() -> Self {
+        Self { last_generated: PipeType::Normal }
+    }
+}
+
+impl ObstacleGenerator {
+    pub fn next_obstacle(&mut self, effects: &Effects, rng: &mut impl Rng) -> PipeType {
+        if effects.middle_pipe_chance > 0. && self.last_generated != PipeType::Middle && rng.gen::<f32>() < effects.middle_pipe_chance {
+            self.last_generated = PipeType::Middle;
+            PipeType::Middle
+        } else {
+            self.last_generated = PipeType::Normal;
+            PipeType::Normal
+        }
+    }
+}
+
+// collisions
+// Axis aligned box given by its center and full size
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Aabb {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        (self.x - other.x).abs() * 2. < self.width + other.width
+            && (self.y - other.y).abs() * 2. < self.height + other.height
+    }
+}
+
+// modifiers
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ModifierKind {
+    Upgrade,
+    Chaos,
+}
+
+// What the active modifiers currently do to the borb and the world
+#[derive(Clone, Debug)]
+pub struct Effects {
+    pub fast_fall_speed: Option<f32>,
+    pub world_speed: f32,
+    pub middle_pipe_chance: f32,
+    // The narrowest gap a pipe pair can be rolled with
+    pub min_half_gap: f32,
+    pub shield: bool,
+    // Seconds of glide on full stamina, None until glide is picked
+    pub glide_stamina: Option<f32>,
+    pub hover_cooldown: Option<f32>,
+    pub bullet_time_duration: Option<f32>,
+    // Multiplier on the borb's sprite and collider
+    pub borb_size: f32,
+    pub wind_strength: Option<f32>,
+    pub pipe_oscillation: Option<Oscillation>,
+    // Seconds between gravity flips
+    pub gravity_flip_interval: Option<f32>,
+    // How far around the borb can be seen, None while the screen is lit
+    pub darkness_radius: Option<f32>,
+    pub scrambling: Option<Scrambling>,
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Self {
+            fast_fall_speed: None,
+            world_speed: PIPE_SPEED,
+            middle_pipe_chance: 0.,
+            min_half_gap: HALF_PIPE_SPACE,
+            shield: false,
+            glide_stamina: None,
+            hover_cooldown: None,
+            bullet_time_duration: None,
+            borb_size: 1.,
+            wind_strength: None,
+            pipe_oscillation: None,
+            gravity_flip_interval: None,
+            darkness_radius: None,
+            scrambling: None,
+        }
+    }
+}
+
+fn fast_fall_speed(level: u32) -> Option<f32> {
+    (level > 0).then(|| FAST_FALL_SPEED + FAST_FALL_SPEED_PER_LEVEL * (level - 1) as f32)
+}
+
+fn glide_stamina(level: u32) -> Option<f32> {
+    (level > 0).then(|| GLIDE_STAMINA + GLIDE_STAMINA_PER_LEVEL * (level - 1) as f32)
+}
+
+fn hover_cooldown(level: u32) -> Option<f32> {
+    (level > 0).then(|| HOVER_COOLDOWN + HOVER_COOLDOWN_PER_LEVEL * (level - 1) as f32)
+}
+
+fn bullet_time_duration(level: u32) -> Option<f32> {
+    (level > 0).then(|| BULLET_TIME_DURATION + BULLET_TIME_DURATION_PER_LEVEL * (level - 1) as f32)
+}
+
+fn wind_strength(level: u32) -> Option<f32> {
+    (level > 0).then(|| WIND_STRENGTH + WIND_STRENGTH_PER_LEVEL * (level - 1) as f32)
+}
+
+fn pipe_oscillation(level: u32) -> Option<Oscillation> {
+    (level > 0).then(|| Oscillation {
+        bob_amplitude: PIPE_BOB_AMPLITUDE + PIPE_BOB_AMPLITUDE_PER_LEVEL * (level - 1) as f32,
+        gap_amplitude: PIPE_GAP_AMPLITUDE + PIPE_GAP_AMPLITUDE_PER_LEVEL * (level - 1) as f32,
+        frequency: PIPE_OSCILLATION_FREQUENCY + PIPE_OSCILLATION_FREQUENCY_PER_LEVEL * (level - 1) as f32,
+    })
+}
+
+fn gravity_flip_interval(level: u32) -> Option<f32> {
+    (level > 0).then(|| GRAVITY_FLIP_TIME + GRAVITY_FLIP_TIME_PER_LEVEL * (level - 1) as f32)
+}
+
+fn darkness_radius(level: u32) -> Option<f32> {
+    (level > 0).then(|| DARKNESS_RADIUS + DARKNESS_RADIUS_PER_LEVEL * (level - 1) as f32)
+}
+
+fn scrambling(level: u32) -> Option<Scrambling> {
+    (level > 0).then(|| Scrambling {
+        duration: CONTROL_SCRAMBLE_TIME + CONTROL_SCRAMBLE_TIME_PER_LEVEL * (level - 1) as f32,
+        delay: CONTROL_DELAY + CONTROL_DELAY_PER_LEVEL * (level - 1) as f32,
+    })
+}
+
+fn min_half_gap(level: u32) -> f32 {
+    (HALF_PIPE_SPACE - NARROW_GAP_PER_LEVEL * level as f32).max(MIN_HALF_PIPE_SPACE)
+}
+
+fn middle_pipe_chance(level: u32) -> f32 {
+    if level == 0 {
+        0.
+    } else {
+        MIDDLE_PIPE_CHANCE + MIDDLE_PIPE_CHANCE_PER_LEVEL * (level - 1) as f32
+    }
+}
+
+// `set` works the effects out from the level the modifier ends up at, after every level gained or lost.
+// `weight` is how likely it is to be drawn before it has any levels, `requires` lists
+// modifiers that have to be active before it can be drawn at all.
+pub struct ModifierDef {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub notification: &'static str,
+    pub kind: ModifierKind,
+    pub max_level: u32,
+    pub weight: u32,
+    pub requires: &'static [&'static str],
+    pub set: fn(&mut Effects, u32),
+}
+
+impl ModifierDef {
+    // The notification text with the level as a roman numeral, "fast pipes II"
+    pub fn notification_at(&self, level: u32) -> String {
+        if level > 1 {
+            format!("{} {}", self.notification, roman_numeral(level))
+        } else {
+            self.notification.to_string()
+        }
+    }
+}
+
+pub fn roman_numeral(mut number: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+        (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+    ];
+    let mut numeral = String::new();
+    for (value, letters) in NUMERALS {
+        while number >= value {
+            numeral.push_str(letters);
+            number -= value;
+        }
+    }
+    numeral
+}
+
+pub const MODIFIERS: &[ModifierDef] = &[
+    ModifierDef {
+        id: "shield",
+        name: "Shield",
+        notification: "shield",
+        kind: ModifierKind::Upgrade,
+        max_level: 1,
+        weight: 8,
+        requires: &[],
+        set: |effects, level| effects.shield = level > 0,
+    },
+    ModifierDef {
+        id: "glide",
+        name: "Glide",
+        notification: "glide",
+        kind: ModifierKind::Upgrade,
+        max_level: 2,
+        weight: 10,
+        requires: &[],
+        set: |effects, level| effects.glide_stamina = glide_stamina(level),
+    },
+    ModifierDef {
+        id: "hover",
+        name: "Hover",
+        notification: "hover",
+        kind: ModifierKind::Upgrade,
+        max_level: 2,
+        weight: 8,
+        requires: &["glide"],
+        set: |effects, level| effects.hover_cooldown = hover_cooldown(level),
+    },
+    ModifierDef {
+        id: "bullet_time",
+        name: "Bullet Time",
+        notification: "bullet time",
+        kind: ModifierKind::Upgrade,
+        max_level: 2,
+        weight: 6,
+        requires: &[],
+        set: |effects, level| effects.bullet_time_duration = bullet_time_duration(level),
+    },
+    ModifierDef {
+        id: "shrink",
+        name: "Shrink",
+        notification: "shrink",
+        kind: ModifierKind::Upgrade,
+        max_level: 2,
+        weight: 8,
+        requires: &[],
+        set: |effects, level| effects.borb_size = SHRINK_PER_LEVEL.powi(level as i32),
+    },
+    ModifierDef {
+        id: "fast_fall",
+        name: "Fast Fall",
+        notification: "fast fall",
+        kind: ModifierKind::Upgrade,
+        max_level: 3,
+        weight: 10,
+        requires: &[],
+        set: |effects, level| effects.fast_fall_speed = fast_fall_speed(level),
+    },
+    ModifierDef {
+        id: "fast_pipes",
+        name: "Fast Pipes",
+        notification: "fast pipes",
+        kind: ModifierKind::Chaos,
+        max_level: 3,
+        weight: 10,
+        requires: &[],
+        set: |effects, level| effects.world_speed = PIPE_SPEED + PIPE_SPEED_PER_LEVEL * level as f32,
+    },
+    ModifierDef {
+        id: "different_pipes",
+        name: "Different Pipes",
+        notification: "different pipes",
+        kind: ModifierKind::Chaos,
+        max_level: 3,
+        weight: 10,
+        requires: &["fast_pipes"],
+        set: |effects, level| effects.middle_pipe_chance = middle_pipe_chance(level),
+    },
+    ModifierDef {
+        id: "wind",
+        name: "Wind",
+        notification: "wind",
+        kind: ModifierKind::Chaos,
+        max_level: 2,
+        weight: 8,
+        requires: &[],
+        set: |effects, level| effects.wind_strength = wind_strength(level),
+    },
+    ModifierDef {
+        id: "moving_pipes",
+        name: "Moving Pipes",
+        notification: "moving pipes",
+        kind: ModifierKind::Chaos,
+        max_level: 3,
+        weight: 8,
+        requires: &[],
+        set: |effects, level| effects.pipe_oscillation = pipe_oscillation(level),
+    },
+    ModifierDef {
+        id: "narrow_gaps",
+        name: "Narrow Gaps",
+        notification: "narrow gaps",
+        kind: ModifierKind::Chaos,
+        max_level: 3,
+        weight: 8,
+        requires: &[],
+        set: |effects, level| effects.min_half_gap = min_half_gap(level),
+    },
+    ModifierDef {
+        id: "gravity_inversion",
+        name: "Gravity Inversion",
+        notification: "gravity inversion",
+        kind: ModifierKind::Chaos,
+        max_level: 2,
+        weight: 6,
+        requires: &[],
+        set: |effects, level| effects.gravity_flip_interval = gravity_flip_interval(level),
+    },
+    ModifierDef {
+        id: "darkness",
+        name: "Darkness",
+        notification: "darkness",
+        kind: ModifierKind::Chaos,
+        max_level: 3,
+        weight: 6,
+        requires: &[],
+        set: |effects, level| effects.darkness_radius = darkness_radius(level),
+    },
+    ModifierDef {
+        id: "scrambled_controls",
+        name: "Scrambled Controls",
+        notification: "scrambled controls",
+        kind: ModifierKind::Chaos,
+        max_level: 2,
+        weight: 6,
+        requires: &[],
+        set: |effects, level| effects.scrambling = scrambling(level),
+    },
+];
+
+fn modifier_index(id: &str) -> Option<usize> {
+    MODIFIERS.iter().position(|modifier| modifier.id == id)
+}
+
+pub fn modifier(id: &str) -> Option<&'static ModifierDef> {
+    modifier_index(id).map(|index| &MODIFIERS[index])
+}
+
+// Levels of every modifier in `MODIFIERS` and what they add up to
+pub struct Modifiers {
+    levels: Vec<u32>,
+    pub effects: Effects,
+    pub num_upgrades: u32,
+    pub num_chaos: u32,
+    upcoming_chaos: Option<&'static ModifierDef>,
+}
+
+impl Default for Modifiers {
+    fn default() -> Self {
+        Self {
+            levels: vec![0; MODIFIERS.len()],
+            effects: Effects::default(),
+            num_upgrades: 0,
+            num_chaos: 0,
+            upcoming_chaos: None,
+        }
+    }
+}
+
+impl Modifiers {
+    pub fn level(&self, id: &str) -> u32 {
+        modifier_index(id).map_or(0, |index| self.levels[index])
+    }
+
+    // How likely a modifier is to be drawn next, it gets rarer with every level it already has
+    // and is never drawn while maxed or missing one of its prerequisites
+    pub fn weight(&self, modifier: &ModifierDef) -> u32 {
+        if !modifier.requires.iter().all(|id| self.level(id) > 0) {
+            return 0;
+        }
+        let level = self.level(modifier.id);
+        modifier.weight * modifier.max_level.saturating_sub(level) / modifier.max_level
+    }
+
+    // Draws up to `count` different modifiers of the given kind without applying them,
+    // empty once that kind is used up
+    pub fn offer(&self, kind: ModifierKind, count: usize, rng: &mut impl Rng) -> Vec<&'static ModifierDef> {
+        let (taken, max) = match kind {
+            ModifierKind::Upgrade => (self.num_upgrades, MAX_UPGRADES),
+            ModifierKind::Chaos => (self.num_chaos, MAX_CHAOS),
+        };
+        if taken == max {
+            return Vec::new();
+        }
+        let mut candidates: Vec<_> = MODIFIERS
+            .iter()
+            .filter(|modifier| modifier.kind == kind)
+            .map(|modifier| (modifier, self.weight(modifier)))
+            .filter(|(_, weight)| *weight > 0)
+            .collect();
+        let mut offered = Vec::new();
+        while offered.len() < count && !candidates.is_empty() {
+            let index = WeightedIndex::new(candidates.iter().map(|(_, weight)| weight))
+                .expect("candidates have positive weights")
+                .sample(rng);
+            offered.push(candidates.remove(index).0);
+        }
+        offered
+    }
+
+    // The chaos the next chaos milestone brings, drawn early so it can be shown ahead of time
+    pub fn upcoming_chaos(&mut self, rng: &mut impl Rng) -> Option<&'static ModifierDef> {
+        if self.upcoming_chaos.is_none() {
+            self.upcoming_chaos = self.offer(ModifierKind::Chaos, 1, rng).pop();
+        }
+        self.upcoming_chaos
+    }
+
+    // Draws the next modifier of the given kind and applies it, None once that kind is used up
+    pub fn grant(&mut self, kind: ModifierKind, rng: &mut impl Rng) -> Option<&'static ModifierDef> {
+        let modifier = match kind {
+            ModifierKind::Upgrade => self.offer(kind, 1, rng).pop(),
+            ModifierKind::Chaos => {
+                self.upcoming_chaos(rng);
+                self.upcoming_chaos.take()
+            }
+        }?;
+        self.take(modifier);
+        Some(modifier)
+    }
+
+    // Applies a modifier that was drawn or picked and counts it towards its kind's maximum
+    pub fn take(&mut self, modifier: &ModifierDef) -> bool {
+        if !self.apply(modifier.id) {
+            return false;
+        }
+        match modifier.kind {
+            ModifierKind::Upgrade => self.num_upgrades += 1,
+            ModifierKind::Chaos => self.num_chaos += 1,
+        }
+        true
+    }
+
+    // Raises a modifier by one level, false if it is unknown or already maxed
+    pub fn apply(&mut self, id: &str) -> bool {
+        let Some(index) = modifier_index(id) else {
+            return false;
+        };
+        let modifier = &MODIFIERS[index];
+        if self.levels[index] == modifier.max_level {
+            return false;
+        }
+        self.levels[index] += 1;
+        (modifier.set)(&mut self.effects, self.levels[index]);
+        true
+    }
+
+    // Uses up the shield if there is one, it can be picked again afterwards
+    pub fn absorb_hit(&mut self) -> bool {
+        self.effects.shield && self.revert("shield")
+    }
+
+    // Takes a modifier down by one level, false if it is unknown or not active
+    pub fn revert(&mut self, id: &str) -> bool {
+        let Some(index) = modifier_index(id) else {
+            return false;
+        };
+        if self.levels[index] == 0 {
+            return false;
+        }
+        self.levels[index] -= 1;
+        (MODIFIERS[index].set)(&mut self.effects, self.levels[index]);
+        true
+    }
+}
+
+// scoring
+pub struct Scoreboard {
+    pub score: u32,
+    was_last_upgrade_good: bool,
+}
+
+impl Default for Scoreboard {
+    fn default() -> Self {
+        Self { score: 0, was_last_upgrade_good: true }
+    }
+}
+
+impl Scoreboard {
+    // Returns the kind of every milestone reached on the way, they alternate between chaos and upgrades
+    pub fn add(&mut self, score: u32) -> Vec<ModifierKind> {
+        let num_upgrades = self.score / PIPES_PER_UPGRADE;
+        self.score += score;
+        (0..(self.score / PIPES_PER_UPGRADE - num_upgrades))
+            .map(|_| {
+                let kind = if self.was_last_upgrade_good {
+                    ModifierKind::Chaos
+                } else {
+                    ModifierKind::Upgrade
+                };
+                self.was_last_upgrade_good = !self.was_last_upgrade_good;
+                kind
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::input::gamepad::{
+    GamepadAxisChangedEvent, GamepadButtonChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadEvent,
+    GamepadInfo,
+};
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::{ButtonState, InputPlugin};
+use bevy::prelude::*;
+use flappy_borb::controls::{Action, ActionInput, ActionMap, MenuDirection, MAX_BINDINGS};
+use std::fs;
+
+#[test]
+fn every_action_starts_bound() {
+    let action_map = ActionMap::default();
+    for action in Action::ALL {
+        assert!(!action_map.keys(action).is_empty(), "{action:?} has no keys");
+        assert!(action_map.keys(action).len() <= MAX_BINDINGS);
+    }
+}
+
+#[test]
+fn rebinding_replaces_or_fills_a_slot() {
+    let mut action_map = ActionMap::default();
+    action_map.rebind(Action::Flap, 0, KeyCode::W);
+    assert_eq!(action_map.keys(Action::Flap)[0], KeyCode::W);
+
+    action_map.rebind(Action::Hover, 1, KeyCode::H);
+    assert_eq!(action_map.keys(Action::Hover), [KeyCode::AltLeft, KeyCode::H]);
+
+    // Already bound keys and slots past the limit are left alone
+    action_map.rebind(Action::Hover, 0, KeyCode::H);
+    action_map.rebind(Action::Hover, MAX_BINDINGS, KeyCode::J);
+    assert_eq!(action_map.keys(Action::Hover), [KeyCode::AltLeft, KeyCode::H]);
+}
+
+#[test]
+fn rebinding_keeps_keys_of_actions_read_together() {
+    let mut action_map = ActionMap::default();
+    // Pause already has escape
+    action_map.rebind(Action::Flap, 0, KeyCode::Escape);
+    assert_eq!(action_map.keys(Action::Flap)[0], KeyCode::Space);
+
+    // Confirm is only read on menus
+    action_map.rebind(Action::Flap, 0, KeyCode::Return);
+    assert_eq!(action_map.keys(Action::Flap)[0], KeyCode::Return);
+}
+
+#[test]
+fn saved_bindings_load_back_and_fill_in_missing_actions() {
+    let path = std::env::temp_dir().join(format!("flappy-borb-controls-{}.ron", std::process::id()));
+    let mut action_map = ActionMap::default();
+    action_map.rebind(Action::FastFall, 0, KeyCode::S);
+    action_map.bindings.remove(&Action::Pause);
+    action_map.save(&path).unwrap();
+
+    let loaded = ActionMap::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.keys(Action::FastFall)[0], KeyCode::S);
+    assert_eq!(loaded.keys(Action::Pause), ActionMap::default().keys(Action::Pause));
+}
+
+#[derive(Resource, Default)]
+struct Seen {
+    directions: Vec<MenuDirection>,
+    confirms: u32,
+}
+
+fn watch_input(mut action_input: ActionInput, mut seen: ResMut<Seen>) {
+    if let Some(direction) = action_input.navigation() {
+        seen.directions.push(direction);
+    }
+    if action_input.just_pressed(Action::Confirm) {
+        seen.confirms += 1;
+    }
+}
+
+fn press_key(app: &mut App, key_code: KeyCode) {
+    app.world.send_event(KeyboardInput { scan_code: 0, key_code: Some(key_code), state: ButtonState::Pressed, window: Entity::PLACEHOLDER });
+    app.update();
+}
+
+fn send(app: &mut App, event: impl Into<GamepadEvent>) {
+    app.world.send_event(event.into());
+    app.update();
+}
+
+#[test]
+fn gamepad_moves_through_menus_and_confirms() {
+    let mut app = App::new();
+    app
+        .add_plugins((MinimalPlugins, InputPlugin))
+        .init_resource::<ActionMap>()
+        .init_resource::<Seen>()
+        .add_systems(Update, watch_input);
+    let gamepad = Gamepad::new(0);
+    let info = GamepadInfo { name: "test pad".into() };
+    send(&mut app, GamepadConnectionEvent::new(gamepad, GamepadConnection::Connected(info)));
+
+    send(&mut app, GamepadButtonChangedEvent::new(gamepad, GamepadButtonType::DPadDown, 1.));
+    send(&mut app, GamepadButtonChangedEvent::new(gamepad, GamepadButtonType::DPadDown, 0.));
+    // Holding the stick only moves once, letting go and pushing again moves again
+    send(&mut app, GamepadAxisChangedEvent::new(gamepad, GamepadAxisType::LeftStickX, 1.));
+    send(&mut app, GamepadAxisChangedEvent::new(gamepad, GamepadAxisType::LeftStickX, 0.9));
+    send(&mut app, GamepadAxisChangedEvent::new(gamepad, GamepadAxisType::LeftStickX, 0.));
+    send(&mut app, GamepadAxisChangedEvent::new(gamepad, GamepadAxisType::LeftStickY, 1.));
+    send(&mut app, GamepadButtonChangedEvent::new(gamepad, GamepadButtonType::South, 1.));
+
+    let seen = app.world.resource::<Seen>();
+    assert_eq!(seen.directions, [MenuDirection::Down, MenuDirection::Right, MenuDirection::Up]);
+    assert_eq!(seen.confirms, 1);
+}
+
+#[test]
+fn arrow_keys_move_through_menus() {
+    let mut app = App::new();
+    app
+        .add_plugins((MinimalPlugins, InputPlugin))
+        .init_resource::<ActionMap>()
+        .init_resource::<Seen>()
+        .add_systems(Update, watch_input);
+    press_key(&mut app, KeyCode::Left);
+    press_key(&mut app, KeyCode::Return);
+
+    let seen = app.world.resource::<Seen>();
+    assert_eq!(seen.directions, [MenuDirection::Left]);
+    assert_eq!(seen.confirms, 1);
+}
@@ -0,0 +1,262 @@
+use bevy::input::gamepad::{
+    GamepadButtonChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadEvent, GamepadInfo,
+};
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::mouse::MouseButtonInput;
+use bevy::input::touch::{TouchInput, TouchPhase};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use flappy_borb::{GameOverCause, GameState, MenuState};
+use std::time::Duration;
+use flappy_borb::game::{Borb, GameOverEvent, Paused, SimulationTick, UpgradeChoice, TICKS_PER_SECOND};
+use flappy_borb::headless::{build_app, simulate, HeadlessOptions};
+use flappy_borb::replay::{Replay, ReplayAction, ReplayInput, ReplayPlayback};
+use flappy_borb::interpolation::Interpolated;
+use flappy_borb::sim::{self, Darkness, Gravity, Modifiers, Scoreboard, DARKNESS_RADIUS, PIPES_PER_UPGRADE};
+
+fn flapping(seed: u64, every: u32) -> HeadlessOptions {
+    let inputs = (1..3000)
+        .step_by(every as usize)
+        .map(|tick| ReplayInput { tick, action: ReplayAction::Jump })
+        .collect();
+    HeadlessOptions {
+        inputs: Some(Replay { seed, inputs, ..Default::default() }),
+        ..Default::default()
+    }
+}
+
+// Starts a run that reads live input instead of a script, it never saves since it never ends
+fn live_app() -> App {
+    let mut app = build_app(&HeadlessOptions { seed: Some(4), ..Default::default() });
+    app.update();
+    app.update();
+    app.world.remove_resource::<ReplayPlayback>();
+    let gamepad = Gamepad::new(0);
+    let info = GamepadInfo { name: "test pad".into() };
+    app.world.send_event(GamepadEvent::from(GamepadConnectionEvent::new(gamepad, GamepadConnection::Connected(info))));
+    app.update();
+    app
+}
+
+fn press(app: &mut App, button_type: GamepadButtonType) {
+    app.world.send_event(GamepadEvent::from(GamepadButtonChangedEvent::new(Gamepad::new(0), button_type, 1.)));
+    app.update();
+}
+
+fn click(app: &mut App, button: MouseButton) {
+    app.world.send_event(MouseButtonInput { button, state: ButtonState::Pressed, window: Entity::PLACEHOLDER });
+    app.update();
+}
+
+fn touch(app: &mut App, id: u64) {
+    app.world.send_event(TouchInput { phase: TouchPhase::Started, position: Vec2::ZERO, force: None, id });
+    app.update();
+}
+
+fn borb_gravity(app: &mut App) -> Gravity {
+    *app.world.query_filtered::<&Gravity, With<Borb>>().single(&app.world)
+}
+
+#[test]
+fn same_seed_and_inputs_give_the_same_run() {
+    let first = simulate(&flapping(11, 40));
+    let second = simulate(&flapping(11, 40));
+    assert_eq!(first.seed, 11);
+    assert_eq!(first.ticks, second.ticks);
+    assert_eq!(first.score, second.score);
+    assert_eq!(first.cause, second.cause);
+}
+
+#[test]
+fn run_without_input_ends() {
+    let outcome = simulate(&HeadlessOptions { seed: Some(3), ..Default::default() });
+    assert_eq!(outcome.score, 0);
+    assert!(outcome.cause.is_some());
+}
+
+#[test]
+fn tick_limit_stops_the_run() {
+    let options = HeadlessOptions { max_ticks: Some(30), ..flapping(5, 40) };
+    let outcome = simulate(&options);
+    assert_eq!(outcome.ticks, 30);
+    assert_eq!(outcome.cause, None);
+}
+
+#[test]
+fn slow_frames_stop_at_the_first_game_over() {
+    let options = flapping(6, 40);
+    let outcome = simulate(&options);
+    let mut app = build_app(&options);
+    // Several ticks a frame, like after a hitch
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(5.0 / TICKS_PER_SECOND)));
+    app.update();
+    app.update();
+    let mut game_overs = 0;
+    while *app.world.resource::<State<GameState>>().get() == GameState::Game {
+        app.update();
+        game_overs += app.world.resource::<Events<GameOverEvent>>().iter_current_update_events().count();
+    }
+    assert_eq!(game_overs, 1);
+    assert_eq!(app.world.resource::<SimulationTick>().0, outcome.ticks);
+    assert_eq!(app.world.resource::<Scoreboard>().score, outcome.score);
+    assert_eq!(*app.world.resource::<State<MenuState>>().get(), MenuState::GameOver(outcome.cause.unwrap()));
+}
+
+#[test]
+fn dying_on_a_milestone_offers_no_upgrade() {
+    // A fresh shield every tick lets the borb fly through pipes, so it gets to the first one
+    fn shielded_update(app: &mut App) {
+        if let Some(mut modifiers) = app.world.get_resource_mut::<Modifiers>() {
+            modifiers.apply("shield");
+        }
+        app.update();
+    }
+    let options = flapping(8, 40);
+    let mut app = build_app(&options);
+    while app.world.get_resource::<Scoreboard>().map_or(0, |score| score.score) == 0 {
+        shielded_update(&mut app);
+    }
+    let pass_tick = app.world.resource::<SimulationTick>().0;
+
+    let mut app = build_app(&options);
+    while app.world.get_resource::<SimulationTick>().map_or(0, |tick| tick.0) < pass_tick - 1 {
+        shielded_update(&mut app);
+    }
+    // The next pipe passed reaches an upgrade milestone, on the tick the borb flies off the screen.
+    // Playback would pick an offered upgrade right away, so stop it to see what is left behind
+    app.world.remove_resource::<ReplayPlayback>();
+    app.world.resource_mut::<Scoreboard>().add(PIPES_PER_UPGRADE * 2 - 1);
+    let (mut transform, mut interpolated) = app
+        .world
+        .query_filtered::<(&mut Transform, &mut Interpolated), With<Borb>>()
+        .single_mut(&mut app.world);
+    transform.translation.y = 10_000.;
+    *interpolated = Interpolated::new(&transform);
+    app.update();
+
+    assert_eq!(app.world.resource::<SimulationTick>().0, pass_tick);
+    assert_eq!(app.world.resource::<Scoreboard>().score, PIPES_PER_UPGRADE * 2);
+    assert!(app.world.get_resource::<UpgradeChoice>().is_none());
+    app.update();
+    assert_eq!(*app.world.resource::<State<MenuState>>().get(), MenuState::GameOver(GameOverCause::TooHigh));
+}
+
+#[test]
+fn playback_picks_an_upgrade_and_resumes() {
+    let mut app = build_app(&flapping(8, 40));
+    app.update();
+    app.update();
+    let fast_fall = sim::modifier("fast_fall").unwrap();
+    app.world.insert_resource(UpgradeChoice { options: vec![fast_fall], upcoming_chaos: None });
+    app.update();
+
+    assert!(app.world.get_resource::<UpgradeChoice>().is_none());
+    assert_eq!(app.world.resource::<Modifiers>().level("fast_fall"), 1);
+    assert!(!app.world.resource::<Time<Virtual>>().is_paused());
+}
+
+#[test]
+fn darkness_flashes_open_on_jumps() {
+    let mut app = build_app(&flapping(8, 40));
+    app.update();
+    app.update();
+    app.world.resource_mut::<Modifiers>().apply("darkness");
+    while app.world.resource::<SimulationTick>().0 < 41 {
+        app.update();
+    }
+    assert!(app.world.resource::<Darkness>().radius > DARKNESS_RADIUS);
+
+    for _ in 0..30 {
+        app.update();
+    }
+    assert_eq!(app.world.resource::<Darkness>().radius, DARKNESS_RADIUS);
+}
+
+#[test]
+fn gamepad_face_button_flaps() {
+    let mut app = live_app();
+    assert!(borb_gravity(&mut app).gravity < 0.);
+    press(&mut app, GamepadButtonType::South);
+    assert!(borb_gravity(&mut app).gravity > 0.);
+}
+
+#[test]
+fn gamepad_start_pauses_and_resumes() {
+    let mut app = live_app();
+    press(&mut app, GamepadButtonType::Start);
+    assert!(app.world.get_resource::<Paused>().is_some());
+    let tick = app.world.resource::<SimulationTick>().0;
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(app.world.resource::<SimulationTick>().0, tick);
+
+    // The button has to be let go before it counts as pressed again
+    app.world.send_event(GamepadEvent::from(GamepadButtonChangedEvent::new(Gamepad::new(0), GamepadButtonType::Start, 0.)));
+    app.update();
+    press(&mut app, GamepadButtonType::Start);
+    assert!(app.world.get_resource::<Paused>().is_none());
+}
+
+#[test]
+fn clicks_flap_unless_they_land_on_a_button() {
+    let mut app = live_app();
+    let button = app.world.spawn((Button, Interaction::Hovered)).id();
+    click(&mut app, MouseButton::Left);
+    assert!(borb_gravity(&mut app).gravity < 0.);
+
+    app.world.despawn(button);
+    app.world.send_event(MouseButtonInput { button: MouseButton::Left, state: ButtonState::Released, window: Entity::PLACEHOLDER });
+    app.update();
+    click(&mut app, MouseButton::Left);
+    assert!(borb_gravity(&mut app).gravity > 0.);
+}
+
+#[test]
+fn single_tap_flaps_right_away() {
+    let mut app = live_app();
+    touch(&mut app, 0);
+    assert!(borb_gravity(&mut app).gravity > 0.);
+}
+
+#[test]
+fn two_finger_tap_turns_the_flap_into_a_dive() {
+    let mut app = live_app();
+    app.world.resource_mut::<Modifiers>().apply("fast_fall");
+    touch(&mut app, 0);
+    touch(&mut app, 1);
+    let gravity = borb_gravity(&mut app);
+    assert!(gravity.gravity < 0.);
+    assert!(gravity.fast_fall_speed.is_some());
+}
+
+#[test]
+fn quick_taps_with_one_finger_each_flap() {
+    let mut app = live_app();
+    app.world.resource_mut::<Modifiers>().apply("fast_fall");
+    touch(&mut app, 0);
+    app.world.send_event(TouchInput { phase: TouchPhase::Ended, position: Vec2::ZERO, force: None, id: 0 });
+    app.update();
+    let falling_back = borb_gravity(&mut app).gravity;
+    // The first finger is already up, so this is another flap even though it is quick
+    touch(&mut app, 1);
+    let gravity = borb_gravity(&mut app);
+    assert!(gravity.gravity > falling_back);
+    assert!(gravity.fast_fall_speed.is_none());
+}
+
+#[test]
+fn presses_while_paused_are_dropped() {
+    let mut app = live_app();
+    press(&mut app, GamepadButtonType::Start);
+    app.world.send_event(KeyboardInput { scan_code: 0, key_code: Some(KeyCode::Space), state: ButtonState::Pressed, window: Entity::PLACEHOLDER });
+    app.update();
+    click(&mut app, MouseButton::Left);
+    app.world.send_event(GamepadEvent::from(GamepadButtonChangedEvent::new(Gamepad::new(0), GamepadButtonType::Start, 0.)));
+    app.update();
+    press(&mut app, GamepadButtonType::Start);
+    assert!(app.world.get_resource::<Paused>().is_none());
+    app.update();
+    assert!(borb_gravity(&mut app).gravity < 0.);
+}
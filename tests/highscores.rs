@@ -0,0 +1,34 @@
+use flappy_borb::game::GameMode;
+use flappy_borb::highscores::{HighScoreEntry, HighScores, MAX_HIGH_SCORES};
+use flappy_borb::GameOverCause;
+
+fn entry(score: u32, seed: u64) -> HighScoreEntry {
+    HighScoreEntry {
+        score,
+        date: "2026-10-17".into(),
+        seed,
+        cause: GameOverCause::HitPipe,
+        mode: GameMode::Normal,
+    }
+}
+
+#[test]
+fn table_keeps_best_scores_in_order() {
+    let mut high_scores = HighScores::default();
+    for (seed, score) in [3, 9, 1, 7, 5, 4].into_iter().enumerate() {
+        high_scores.insert(entry(score, seed as u64));
+    }
+    let scores: Vec<u32> = high_scores.entries.iter().map(|entry| entry.score).collect();
+    assert_eq!(scores, [9, 7, 5, 4, 3]);
+    assert_eq!(high_scores.entries.len(), MAX_HIGH_SCORES);
+    assert_eq!(high_scores.insert(entry(2, 10)), None);
+    assert_eq!(high_scores.best(), Some(9));
+}
+
+#[test]
+fn ties_go_below_older_entries() {
+    let mut high_scores = HighScores::default();
+    high_scores.insert(entry(5, 1));
+    assert_eq!(high_scores.insert(entry(5, 2)), Some(1));
+    assert_eq!(high_scores.entries[0].seed, 1);
+}
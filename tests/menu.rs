@@ -0,0 +1,95 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use flappy_borb::headless::{build_app, HeadlessOptions};
+use flappy_borb::highscores::{HighScores, LastRun};
+use flappy_borb::menu::MenuPlugin;
+use flappy_borb::{GameState, MenuState};
+
+// A headless run left alone until it ends on the game over screen
+fn game_over_app() -> App {
+    let mut app = build_app(&HeadlessOptions { seed: Some(3), ..Default::default() });
+    app.init_resource::<HighScores>().init_resource::<LastRun>();
+    // The headless app is already finished, so build the menus into it by hand
+    MenuPlugin.build(&mut app);
+    app.update();
+    while !matches!(app.world.resource::<State<MenuState>>().get(), MenuState::GameOver(_)) {
+        app.update();
+    }
+    app.update();
+    app
+}
+
+fn key(app: &mut App, key_code: KeyCode, state: ButtonState) {
+    app.world.send_event(KeyboardInput { scan_code: 0, key_code: Some(key_code), state, window: Entity::PLACEHOLDER });
+    app.update();
+}
+
+fn tap(app: &mut App, key_code: KeyCode) {
+    key(app, key_code, ButtonState::Pressed);
+    key(app, key_code, ButtonState::Released);
+}
+
+fn label(app: &mut App, button: Entity) -> String {
+    let children = app.world.get::<Children>(button).unwrap();
+    app.world.get::<Text>(children[0]).unwrap().sections[0].value.clone()
+}
+
+// The label of the button drawn with a focus border
+fn focused(app: &mut App) -> Option<String> {
+    let button = app
+        .world
+        .query_filtered::<(Entity, &BorderColor), With<Button>>()
+        .iter(&app.world)
+        .find(|(_, border_color)| border_color.0 != Color::NONE)
+        .map(|(entity, _)| entity)?;
+    Some(label(app, button))
+}
+
+#[test]
+fn tab_cycles_through_buttons_in_reading_order() {
+    let mut app = game_over_app();
+    // Without layout every button sits at the origin, so lay them out in a row by hand
+    let buttons: Vec<Entity> = app.world.query_filtered::<Entity, With<Button>>().iter(&app.world).collect();
+    for button in buttons {
+        let x = match label(&mut app, button).as_str() {
+            "Same Seed" => 0.,
+            "Replay" => 100.,
+            _ => 200.,
+        };
+        app.world.entity_mut(button).insert(GlobalTransform::from_xyz(x, 0., 0.));
+    }
+    assert_eq!(focused(&mut app), None);
+
+    tap(&mut app, KeyCode::Tab);
+    assert_eq!(focused(&mut app).as_deref(), Some("Same Seed"));
+    tap(&mut app, KeyCode::Tab);
+    assert_eq!(focused(&mut app).as_deref(), Some("Replay"));
+
+    key(&mut app, KeyCode::ShiftLeft, ButtonState::Pressed);
+    tap(&mut app, KeyCode::Tab);
+    assert_eq!(focused(&mut app).as_deref(), Some("Same Seed"));
+    tap(&mut app, KeyCode::Tab);
+    assert_eq!(focused(&mut app).as_deref(), Some("Main Menu"));
+}
+
+#[test]
+fn escape_leaves_the_game_over_screen() {
+    let mut app = game_over_app();
+    tap(&mut app, KeyCode::Escape);
+    assert_eq!(*app.world.resource::<State<MenuState>>().get(), MenuState::MainMenu);
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::Menu);
+}
+
+#[test]
+fn space_on_the_game_over_screen_restarts_after_a_moment() {
+    let mut app = game_over_app();
+    // A flap carried over from the run that just ended is ignored
+    tap(&mut app, KeyCode::Space);
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::Menu);
+    for _ in 0..60 {
+        app.update();
+    }
+    tap(&mut app, KeyCode::Space);
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::Game);
+}
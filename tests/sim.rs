@@ -0,0 +1,471 @@
+use flappy_borb::sim::{
+    self, Aabb, BulletTime, ControlScramble, Glide, Gravity, GravityFlip, Hover, ModifierKind, Modifiers,
+    ObstacleGenerator, OscillatingPipe, PipeEvent, PipeMotion, PipeType, Scoreboard, Scramble, Wind,
+    WindPhase, BULLET_TIME_COOLDOWN, BULLET_TIME_SCALE, FAST_FALL_SPEED, GLIDE_FALL_SPEED, GLIDE_STAMINA,
+    GRAVITY_FLIP_TIME, HALF_PIPE_SPACE, HALF_SCREEN_HEIGHT, HALF_SCREEN_WIDTH_WITH_HALF_PIPE, HOVER_COOLDOWN,
+    JUMP_FORCE, MAX_CHAOS, MAX_GRAVITY, MAX_PIPE_HOLE_Y, MIN_HALF_PIPE_SPACE, PIPES_PER_UPGRADE, PIPE_SPEED,
+    SHRINK_PER_LEVEL, UPGRADE_CHOICES, WIND_STRENGTH,
+};
+use flappy_borb::GameOverCause;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+const DELTA: f32 = 1. / 60.;
+
+#[test]
+fn falling_is_capped_at_max_gravity() {
+    let mut gravity = Gravity::default();
+    for _ in 0..600 {
+        gravity.step(DELTA);
+    }
+    assert_eq!(gravity.gravity, MAX_GRAVITY);
+}
+
+#[test]
+fn fast_fall_keeps_its_own_cap() {
+    let mut gravity = Gravity::default();
+    gravity.fast_fall(FAST_FALL_SPEED);
+    for _ in 0..600 {
+        gravity.step(DELTA);
+    }
+    assert_eq!(gravity.gravity, FAST_FALL_SPEED);
+}
+
+#[test]
+fn jump_moves_up_then_falls_back() {
+    let mut gravity = Gravity::default();
+    gravity.jump();
+    assert_eq!(gravity.gravity, JUMP_FORCE);
+    assert!(gravity.step(DELTA) > 0.);
+    for _ in 0..120 {
+        gravity.step(DELTA);
+    }
+    assert!(gravity.step(DELTA) < 0.);
+}
+
+#[test]
+fn leaving_the_screen_ends_the_run() {
+    assert_eq!(sim::out_of_bounds(0., 18.), None);
+    assert_eq!(sim::out_of_bounds(HALF_SCREEN_HEIGHT + 10., 18.), None);
+    assert_eq!(sim::out_of_bounds(HALF_SCREEN_HEIGHT + 20., 18.), Some(GameOverCause::TooHigh));
+    assert_eq!(sim::out_of_bounds(-HALF_SCREEN_HEIGHT - 20., 18.), Some(GameOverCause::TooLow));
+}
+
+#[test]
+fn glide_slows_the_fall_until_stamina_runs_out() {
+    let mut gravity = Gravity::default();
+    let mut glide = Glide::default();
+    let mut ticks = 0;
+    loop {
+        glide.step(&mut gravity, true, GLIDE_STAMINA, DELTA);
+        gravity.step(DELTA);
+        if !glide.gliding {
+            break;
+        }
+        assert!(gravity.gravity >= GLIDE_FALL_SPEED);
+        ticks += 1;
+    }
+    assert!(ticks as f32 * DELTA >= GLIDE_STAMINA - DELTA);
+    for _ in 0..600 {
+        glide.step(&mut gravity, true, GLIDE_STAMINA, DELTA);
+        gravity.step(DELTA);
+    }
+    assert_eq!(gravity.gravity, MAX_GRAVITY);
+
+    glide.step(&mut gravity, false, GLIDE_STAMINA, 1.);
+    assert!(glide.stamina > 0.);
+}
+
+#[test]
+fn hover_holds_still_then_cools_down() {
+    let mut gravity = Gravity { gravity: -100., ..Default::default() };
+    let mut hover = Hover::default();
+    assert!(hover.trigger(&mut gravity, HOVER_COOLDOWN));
+    hover.step(&mut gravity, DELTA);
+    assert_eq!(gravity.step(DELTA), 0.);
+    assert!(!hover.trigger(&mut gravity, HOVER_COOLDOWN));
+
+    gravity.jump();
+    hover.step(&mut gravity, DELTA);
+    assert!(gravity.step(DELTA) > 0.);
+
+    for _ in 0..(HOVER_COOLDOWN / DELTA) as u32 + 1 {
+        hover.step(&mut gravity, DELTA);
+    }
+    assert!(hover.trigger(&mut gravity, HOVER_COOLDOWN));
+}
+
+#[test]
+fn bullet_time_slows_down_then_cools_down() {
+    let mut bullet_time = BulletTime::default();
+    assert_eq!(bullet_time.step(DELTA), 1.);
+    assert!(bullet_time.trigger(1.));
+    assert_eq!(bullet_time.step(DELTA), BULLET_TIME_SCALE);
+    assert!(!bullet_time.trigger(1.));
+    for _ in 0..60 {
+        bullet_time.step(DELTA);
+    }
+    assert_eq!(bullet_time.step(DELTA), 1.);
+    assert!(!bullet_time.trigger(1.));
+    for _ in 0..(BULLET_TIME_COOLDOWN / DELTA) as u32 {
+        bullet_time.step(DELTA);
+    }
+    assert!(bullet_time.trigger(1.));
+}
+
+#[test]
+fn size_tween_settles_on_its_target() {
+    let mut size = 1.;
+    let mut previous = size;
+    for _ in 0..120 {
+        size = sim::tween_size(size, SHRINK_PER_LEVEL, DELTA);
+        assert!(size <= previous && size >= SHRINK_PER_LEVEL);
+        previous = size;
+    }
+    assert_eq!(size, SHRINK_PER_LEVEL);
+}
+
+#[test]
+fn wind_warns_before_each_gust() {
+    let mut rng = ChaCha8Rng::seed_from_u64(9);
+    let mut wind = Wind::default();
+    for _ in 0..600 {
+        wind.step(None, &mut rng, DELTA);
+    }
+    assert_eq!(wind.phase, WindPhase::Calm);
+
+    let mut phases = vec![wind.phase];
+    for _ in 0..60 * 30 {
+        wind.step(Some(WIND_STRENGTH), &mut rng, DELTA);
+        if phases.last() != Some(&wind.phase) {
+            phases.push(wind.phase);
+        }
+        if wind.phase != WindPhase::Gust {
+            assert_eq!(wind.velocity(), (0., 0.));
+        }
+    }
+    assert!(phases.contains(&WindPhase::Gust));
+    for pair in phases.windows(2) {
+        match pair[1] {
+            WindPhase::Gust => assert_eq!(pair[0], WindPhase::Warning),
+            WindPhase::Warning => assert_eq!(pair[0], WindPhase::Calm),
+            WindPhase::Calm => assert_eq!(pair[0], WindPhase::Gust),
+        }
+    }
+}
+
+#[test]
+fn drift_springs_back_home() {
+    let home = -200.;
+    let mut x = home;
+    for _ in 0..60 {
+        x = sim::drift(x, home, WIND_STRENGTH, DELTA);
+    }
+    assert!(x > home);
+    for _ in 0..600 {
+        x = sim::drift(x, home, 0., DELTA);
+    }
+    assert!((x - home).abs() < 0.1);
+}
+
+#[test]
+fn pipe_scores_once_then_wraps() {
+    let borb_x = -200.;
+    let mut x = borb_x + 0.5;
+    let mut passed = false;
+    assert_eq!(sim::scroll_pipe(&mut x, &mut passed, borb_x, PIPE_SPEED, DELTA), PipeEvent::Passed);
+    assert_eq!(sim::scroll_pipe(&mut x, &mut passed, borb_x, PIPE_SPEED, DELTA), PipeEvent::Moved);
+
+    let mut events = Vec::new();
+    while events.last() != Some(&PipeEvent::Wrapped) {
+        events.push(sim::scroll_pipe(&mut x, &mut passed, borb_x, PIPE_SPEED, DELTA));
+    }
+    assert!(!events.contains(&PipeEvent::Passed));
+    assert_eq!(x, HALF_SCREEN_WIDTH_WITH_HALF_PIPE);
+    assert!(!passed);
+}
+
+#[test]
+fn boxes_only_collide_when_overlapping() {
+    let borb = Aabb::new(0., 0., 30., 30.);
+    assert!(borb.overlaps(&Aabb::new(20., 20., 30., 30.)));
+    assert!(!borb.overlaps(&Aabb::new(30., 0., 30., 30.)));
+    assert!(!borb.overlaps(&Aabb::new(0., -40., 30., 30.)));
+}
+
+#[test]
+fn milestones_alternate_between_chaos_and_upgrades() {
+    let mut score = Scoreboard::default();
+
+    assert!(score.add(PIPES_PER_UPGRADE - 1).is_empty());
+    assert_eq!(score.add(1), [ModifierKind::Chaos]);
+    assert_eq!(score.add(PIPES_PER_UPGRADE), [ModifierKind::Upgrade]);
+    assert_eq!(score.add(PIPES_PER_UPGRADE * 2), [ModifierKind::Chaos, ModifierKind::Upgrade]);
+    assert_eq!(score.score, PIPES_PER_UPGRADE * 4);
+}
+
+#[test]
+fn offers_are_distinct_and_leave_modifiers_alone() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4);
+    let mut modifiers = Modifiers::default();
+    let offer = modifiers.offer(ModifierKind::Upgrade, UPGRADE_CHOICES, &mut rng);
+    assert!(!offer.is_empty() && offer.len() <= UPGRADE_CHOICES);
+    assert!(offer.iter().all(|upgrade| upgrade.kind == ModifierKind::Upgrade));
+    for (index, upgrade) in offer.iter().enumerate() {
+        assert!(offer[index + 1..].iter().all(|other| other.id != upgrade.id));
+        assert_eq!(modifiers.level(upgrade.id), 0);
+    }
+
+    assert!(modifiers.take(offer[0]));
+    assert_eq!(modifiers.num_upgrades, 1);
+}
+
+#[test]
+fn upcoming_chaos_is_the_one_granted() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    let mut modifiers = Modifiers::default();
+    let upcoming = modifiers.upcoming_chaos(&mut rng).unwrap();
+    assert_eq!(modifiers.upcoming_chaos(&mut rng).unwrap().id, upcoming.id);
+    assert_eq!(modifiers.grant(ModifierKind::Chaos, &mut rng).unwrap().id, upcoming.id);
+    assert_eq!(modifiers.level(upcoming.id), 1);
+}
+
+#[test]
+fn modifiers_stop_at_their_max_level() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5);
+    let mut modifiers = Modifiers::default();
+    for _ in 0..MAX_CHAOS {
+        modifiers.grant(ModifierKind::Chaos, &mut rng);
+    }
+    assert!(modifiers.grant(ModifierKind::Chaos, &mut rng).is_none());
+    assert!(modifiers.num_chaos <= MAX_CHAOS);
+    assert!(sim::MODIFIERS
+        .iter()
+        .all(|modifier| modifiers.level(modifier.id) <= modifier.max_level));
+}
+
+#[test]
+fn modifiers_wait_for_their_prerequisites() {
+    let mut modifiers = Modifiers::default();
+    for modifier in sim::MODIFIERS.iter().filter(|modifier| !modifier.requires.is_empty()) {
+        assert_eq!(modifiers.weight(modifier), 0);
+    }
+    for modifier in sim::MODIFIERS {
+        for id in modifier.requires {
+            modifiers.apply(id);
+        }
+    }
+    for modifier in sim::MODIFIERS.iter().filter(|modifier| modifiers.level(modifier.id) == 0) {
+        assert!(modifiers.weight(modifier) > 0);
+    }
+}
+
+#[test]
+fn same_rng_draws_the_same_modifiers() {
+    let draw = |seed| {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut modifiers = Modifiers::default();
+        (0..MAX_CHAOS)
+            .filter_map(|_| modifiers.grant(ModifierKind::Chaos, &mut rng))
+            .map(|modifier| modifier.id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(draw(6), draw(6));
+}
+
+#[test]
+fn reverting_a_modifier_undoes_its_effect() {
+    let mut modifiers = Modifiers::default();
+    assert!(modifiers.apply("fast_pipes"));
+    assert_eq!(modifiers.level("fast_pipes"), 1);
+    assert!(modifiers.revert("fast_pipes"));
+    assert_eq!(modifiers.effects.world_speed, PIPE_SPEED);
+    assert!(!modifiers.revert("fast_pipes"));
+    assert!(!modifiers.apply("no_such_modifier"));
+}
+
+#[test]
+fn levels_stack_and_show_in_notifications() {
+    let mut modifiers = Modifiers::default();
+    let fast_pipes = sim::modifier("fast_pipes").unwrap();
+    let speeds: Vec<f32> = (0..fast_pipes.max_level)
+        .map(|_| {
+            modifiers.apply(fast_pipes.id);
+            modifiers.effects.world_speed
+        })
+        .collect();
+    assert_eq!(speeds, [75., 100., 125.]);
+    assert_eq!(fast_pipes.notification_at(1), "fast pipes");
+    assert_eq!(fast_pipes.notification_at(2), "fast pipes II");
+    assert_eq!(sim::roman_numeral(4), "IV");
+
+    modifiers.apply("fast_fall");
+    let first_dive = modifiers.effects.fast_fall_speed.unwrap();
+    modifiers.apply("fast_fall");
+    assert!(modifiers.effects.fast_fall_speed.unwrap() < first_dive);
+    modifiers.revert("fast_fall");
+    assert_eq!(modifiers.effects.fast_fall_speed, Some(first_dive));
+}
+
+#[test]
+fn shield_absorbs_one_hit_and_can_return() {
+    let mut modifiers = Modifiers::default();
+    assert!(!modifiers.absorb_hit());
+    modifiers.apply("shield");
+    assert!(modifiers.effects.shield);
+    assert!(modifiers.absorb_hit());
+    assert!(!modifiers.effects.shield);
+    assert!(!modifiers.absorb_hit());
+    assert!(modifiers.weight(sim::modifier("shield").unwrap()) > 0);
+}
+
+#[test]
+fn middle_pipes_need_chaos_and_never_repeat() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let mut generator = ObstacleGenerator::default();
+    let mut modifiers = Modifiers::default();
+    for _ in 0..200 {
+        assert_eq!(generator.next_obstacle(&modifiers.effects, &mut rng), PipeType::Normal);
+    }
+
+    modifiers.apply("different_pipes");
+    let obstacles: Vec<_> = (0..500).map(|_| generator.next_obstacle(&modifiers.effects, &mut rng)).collect();
+    assert!(obstacles.contains(&PipeType::Middle));
+    assert!(obstacles.windows(2).all(|pair| pair != [PipeType::Middle, PipeType::Middle]));
+}
+
+#[test]
+fn pipe_holes_stay_in_range() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    for _ in 0..1000 {
+        let y = sim::random_pipe_hole_y(&mut rng);
+        assert!((-MAX_PIPE_HOLE_Y..=MAX_PIPE_HOLE_Y).contains(&y));
+    }
+}
+
+#[test]
+fn pipes_only_move_with_moving_pipes() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let mut modifiers = Modifiers::default();
+    let mut pipe = OscillatingPipe::new(PipeType::Normal, 10., HALF_PIPE_SPACE, modifiers.effects.pipe_oscillation, &mut rng);
+    assert_eq!(pipe.motion, PipeMotion::Still);
+    for _ in 0..120 {
+        assert_eq!(pipe.step(modifiers.effects.pipe_oscillation, DELTA), (10., HALF_PIPE_SPACE));
+    }
+
+    modifiers.apply("moving_pipes");
+    let level_one = modifiers.effects.pipe_oscillation.unwrap();
+    modifiers.apply("moving_pipes");
+    let level_two = modifiers.effects.pipe_oscillation.unwrap();
+    assert!(level_two.bob_amplitude > level_one.bob_amplitude);
+    assert!(level_two.gap_amplitude > level_one.gap_amplitude);
+    assert!(level_two.frequency > level_one.frequency);
+
+    let mut motions = vec![];
+    for _ in 0..50 {
+        let hole_y = sim::random_pipe_hole_y(&mut rng);
+        let mut pipe = OscillatingPipe::new(PipeType::Normal, hole_y, HALF_PIPE_SPACE, Some(level_two), &mut rng);
+        motions.push(pipe.motion);
+        for _ in 0..300 {
+            let (y, half_gap) = pipe.step(Some(level_two), DELTA);
+            assert!((-MAX_PIPE_HOLE_Y..=MAX_PIPE_HOLE_Y).contains(&y));
+            assert!((half_gap - HALF_PIPE_SPACE).abs() <= level_two.gap_amplitude);
+        }
+    }
+    assert!(motions.contains(&PipeMotion::Bob));
+    assert!(motions.contains(&PipeMotion::Breathe));
+
+    let middle = OscillatingPipe::new(PipeType::Middle, 0., HALF_PIPE_SPACE, Some(level_two), &mut rng);
+    assert_eq!(middle.motion, PipeMotion::Bob);
+}
+
+#[test]
+fn narrow_gaps_never_go_below_the_minimum() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4);
+    let mut modifiers = Modifiers::default();
+    assert_eq!(sim::random_pipe_half_gap(modifiers.effects.min_half_gap, &mut rng), HALF_PIPE_SPACE);
+
+    let mut narrowest = HALF_PIPE_SPACE;
+    while modifiers.apply("narrow_gaps") {
+        assert!(modifiers.effects.min_half_gap < narrowest);
+        narrowest = modifiers.effects.min_half_gap;
+    }
+    assert!(narrowest >= MIN_HALF_PIPE_SPACE);
+
+    modifiers.apply("moving_pipes");
+    modifiers.apply("moving_pipes");
+    let oscillation = modifiers.effects.pipe_oscillation;
+    for _ in 0..100 {
+        let half_gap = sim::random_pipe_half_gap(modifiers.effects.min_half_gap, &mut rng);
+        assert!((narrowest..=HALF_PIPE_SPACE).contains(&half_gap));
+        let mut pipe = OscillatingPipe::new(PipeType::Normal, 0., half_gap, oscillation, &mut rng);
+        for _ in 0..300 {
+            assert!(pipe.step(oscillation, DELTA).1 >= MIN_HALF_PIPE_SPACE);
+        }
+    }
+}
+
+#[test]
+fn gravity_flips_after_a_warning_and_jumps_follow_it() {
+    let mut gravity = Gravity::default();
+    let mut gravity_flip = GravityFlip::default();
+    for _ in 0..600 {
+        assert!(!gravity_flip.step(&mut gravity, None, DELTA));
+    }
+    assert!(gravity_flip.warning().is_none());
+
+    let mut warnings = vec![];
+    while !gravity_flip.step(&mut gravity, Some(GRAVITY_FLIP_TIME), DELTA) {
+        if let Some(seconds) = gravity_flip.warning() {
+            if warnings.last() != Some(&seconds) {
+                warnings.push(seconds);
+            }
+        }
+    }
+    assert_eq!(warnings, [3, 2, 1]);
+    assert!(gravity.inverted);
+
+    // Falls up, and jumping pushes down
+    assert!(gravity.step(DELTA) > 0.);
+    gravity.jump();
+    assert!(gravity.step(DELTA) < 0.);
+
+    // Losing the effect puts gravity back the right way up
+    assert!(gravity_flip.step(&mut gravity, None, DELTA));
+    assert!(!gravity.inverted);
+    assert!(gravity.step(DELTA) < 0.);
+}
+
+#[test]
+fn scrambled_controls_swap_or_delay_jumps() {
+    let mut rng = ChaCha8Rng::seed_from_u64(6);
+    let mut scramble = ControlScramble::default();
+    for _ in 0..600 {
+        assert_eq!(scramble.step(None, &mut rng, DELTA), None);
+        assert_eq!(scramble.route(true, false, None, DELTA), (true, false));
+    }
+
+    let mut modifiers = Modifiers::default();
+    modifiers.apply("scrambled_controls");
+    let scrambling = modifiers.effects.scrambling;
+    let mut seen = vec![];
+    for _ in 0..60 * 60 {
+        if let Some(started) = scramble.step(scrambling, &mut rng, DELTA) {
+            seen.push(started);
+        }
+        match scramble.active {
+            Some(Scramble::Reversed) => assert_eq!(scramble.route(true, false, scrambling, DELTA), (false, true)),
+            Some(Scramble::Delayed) => {
+                assert_eq!(scramble.route(true, false, scrambling, DELTA), (false, false));
+                let mut ticks = 0;
+                while !scramble.route(false, false, scrambling, DELTA).0 {
+                    ticks += 1;
+                }
+                assert!((ticks as f32 * DELTA - scrambling.unwrap().delay).abs() <= DELTA);
+            }
+            None => assert_eq!(scramble.route(true, false, scrambling, DELTA), (true, false)),
+        }
+    }
+    assert!(seen.contains(&Scramble::Reversed));
+    assert!(seen.contains(&Scramble::Delayed));
+}